@@ -0,0 +1,145 @@
+use crate::display::Display;
+use crate::menu;
+use tetrust::board::{Board, BOARD_HEIGHT, BOARD_WIDTH, HIDDEN_ROWS};
+use tetrust::mode::Mode;
+use tetrust::piece::Piece;
+use tetrust::render::{format_time, Overlay, Renderer, Stats};
+use tetrust::util::{Color, Point};
+
+/// Where the side panel with the score, the hold slot and clear announcements starts.
+const LEFT_MARGIN: u32 = BOARD_WIDTH * 2 + 5;
+/// Where the column of next pieces starts.
+const NEXT_MARGIN: u32 = LEFT_MARGIN + 14;
+/// Where the mode's clock and goal are shown.
+const MODE_MARGIN: u32 = LEFT_MARGIN + 28;
+
+/// Draws games onto the terminal display, with the board on the left and two character wide cells.
+pub struct AnsiRenderer<'a> {
+    display: &'a mut Display,
+}
+
+impl<'a> AnsiRenderer<'a> {
+    pub fn new(display: &'a mut Display) -> AnsiRenderer<'a> {
+        AnsiRenderer { display }
+    }
+
+    fn render_piece(&mut self, piece: &Piece, origin: Point) {
+        let color = piece.color();
+        let display = &mut *self.display;
+
+        piece.each_point(&mut |row, col| {
+            // A piece that locked out leaves its ghost above the top of the screen
+            if origin.y + row < 0 {
+                return;
+            }
+            let x = (origin.x + 2 * col) as u32;
+            let y = (origin.y + row) as u32;
+            display.set_text(" ", x, y, color, color);
+            display.set_text(" ", x + 1, y, color, color);
+        });
+    }
+}
+
+impl Renderer for AnsiRenderer<'_> {
+    fn board(&mut self, board: &Board) {
+        let display = &mut *self.display;
+        for y in HIDDEN_ROWS..BOARD_HEIGHT {
+            display.set_text("|", 0, y, Color::Red, Color::Black);
+            display.set_text("|", BOARD_WIDTH * 2 + 1, y, Color::Red, Color::Black);
+        }
+        for x in 0..(BOARD_WIDTH * 2 + 1) {
+            display.set_text("-", x, BOARD_HEIGHT, Color::Red, Color::Black);
+        }
+        for row in 0..BOARD_HEIGHT {
+            for col in 0..BOARD_WIDTH {
+                if let Some(cell) = board.get(col, row) {
+                    let color = cell.color();
+                    let c = 1 + (col * 2);
+                    display.set_text(" ", c, row, color, color);
+                    display.set_text(" ", c + 1, row, color, color);
+                }
+            }
+        }
+    }
+
+    fn active_piece(&mut self, piece: &Piece, position: Point) {
+        self.render_piece(piece, Point{ x: 1 + 2 * position.x, y: position.y });
+    }
+
+    fn ghost(&mut self, piece: &Piece, position: Point) {
+        self.render_piece(piece, Point{ x: 1 + 2 * position.x, y: position.y });
+    }
+
+    fn queue(&mut self, pieces: &[Piece]) {
+        self.display.set_text("Next piece:", NEXT_MARGIN, 2, Color::Red, Color::Black);
+        for (i, piece) in pieces.iter().enumerate() {
+            self.render_piece(piece, Point{ x: (NEXT_MARGIN as i32) + 2, y: 4 + 3 * (i as i32) });
+        }
+    }
+
+    fn hold(&mut self, piece: Option<&Piece>, can_hold: bool) {
+        // Grey the slot out while it can't be swapped back in
+        let hold_color = if can_hold { Color::Red } else { Color::Grey };
+        self.display.set_text("Hold:", LEFT_MARGIN, 7, hold_color, Color::Black);
+        if let Some(piece) = piece {
+            self.render_piece(piece, Point{ x: (LEFT_MARGIN as i32) + 2, y: 9 });
+        }
+    }
+
+    fn stats(&mut self, stats: &Stats) {
+        let display = &mut *self.display;
+        let level_line = format!("Level: {}", stats.level);
+        display.set_text(&level_line, LEFT_MARGIN, 3, Color::Red, Color::Black);
+        let score_line = format!("Score: {}", stats.score);
+        display.set_text(&score_line, LEFT_MARGIN, 4, Color::Red, Color::Black);
+
+        // Render the mode's clock and goal
+        if let Mode::Ultra { time_limit } = stats.mode {
+            display.set_text("Ultra", MODE_MARGIN, 2, Color::Red, Color::Black);
+            let remaining = format!("Time left: {}", format_time(time_limit.saturating_sub(stats.time)));
+            display.set_text(&remaining, MODE_MARGIN, 4, Color::Red, Color::Black);
+            let lines = format!("Lines: {}", stats.lines);
+            display.set_text(&lines, MODE_MARGIN, 5, Color::Red, Color::Black);
+        }
+        if let Mode::Sprint { lines } = stats.mode {
+            display.set_text(&format!("Sprint {}", lines), MODE_MARGIN, 2, Color::Red, Color::Black);
+            let time = format!("Time: {}", format_time(stats.time));
+            display.set_text(&time, MODE_MARGIN, 4, Color::Red, Color::Black);
+            let progress = format!("Lines: {}/{}", stats.lines, lines);
+            display.set_text(&progress, MODE_MARGIN, 5, Color::Red, Color::Black);
+
+            display.set_text("Splits:", MODE_MARGIN, 7, Color::Red, Color::Black);
+            for (i, split) in stats.splits.iter().enumerate() {
+                let split = format!("{:>3}  {}", (i + 1) * 10, format_time(*split));
+                display.set_text(&split, MODE_MARGIN, 8 + i as u32, Color::Blue, Color::Black);
+            }
+        }
+    }
+
+    fn overlay(&mut self, overlay: &Overlay) {
+        let display = &mut *self.display;
+        match overlay {
+            Overlay::Clear(clear) => {
+                display.set_text(&clear.description(), LEFT_MARGIN, 14, Color::Purple, Color::Black);
+                let points = format!("+{}", clear.points);
+                display.set_text(&points, LEFT_MARGIN, 15, Color::Red, Color::Black);
+                if clear.back_to_back {
+                    display.set_text("Back-to-Back", LEFT_MARGIN, 16, Color::Orange, Color::Black);
+                }
+                if clear.combo > 0 {
+                    let combo = format!("{} Combo", clear.combo);
+                    display.set_text(&combo, LEFT_MARGIN, 17, Color::Green, Color::Black);
+                }
+                if clear.perfect_clear {
+                    let banner = "PERFECT CLEAR";
+                    let x = 1 + (BOARD_WIDTH * 2 - banner.len() as u32) / 2;
+                    display.set_text(banner, x, BOARD_HEIGHT / 2, Color::Black, Color::Orange);
+                }
+            },
+            Overlay::Paused { options, selected } => {
+                display.set_text("PAUSED", 1 + (BOARD_WIDTH * 2 - 6) / 2, 7, Color::Orange, Color::Black);
+                menu::render_options(display, options, *selected, 6, 9);
+            },
+        }
+    }
+}
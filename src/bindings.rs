@@ -0,0 +1,324 @@
+use crate::input::Key;
+use std::fmt;
+use std::str::FromStr;
+use tetrust::Input;
+
+/// What the player can do while playing.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Rotate180,
+        Action::Hold,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+    ];
+
+    /// Returns the name of the action in the key bindings file, such as `move_left`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::RotateCw => "rotate_cw",
+            Action::RotateCcw => "rotate_ccw",
+            Action::Rotate180 => "rotate_180",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// Returns the name of the action shown to the player, such as "Move left".
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::SoftDrop => "Soft drop",
+            Action::HardDrop => "Hard drop",
+            Action::RotateCw => "Rotate right",
+            Action::RotateCcw => "Rotate left",
+            Action::Rotate180 => "Rotate 180",
+            Action::Hold => "Hold",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::Quit => "Quit to menu",
+        }
+    }
+
+    /// Returns the engine input the action triggers, or `None` for the actions the frontend handles itself.
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Action::MoveLeft => Some(Input::MoveLeft),
+            Action::MoveRight => Some(Input::MoveRight),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::HardDrop),
+            Action::RotateCw => Some(Input::RotateCw),
+            Action::RotateCcw => Some(Input::RotateCcw),
+            Action::Rotate180 => Some(Input::Rotate180),
+            Action::Hold => Some(Input::Hold),
+            Action::Pause | Action::Restart | Action::Quit => None,
+        }
+    }
+
+    fn index(&self) -> usize {
+        Action::ALL.iter().position(|action| action == self).expect("ALL lists every action")
+    }
+}
+
+/// The keys bound to each action. Any number of keys can be bound to an action, but each key to only one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bindings {
+    /// The keys bound to each action, indexed like `Action::ALL`.
+    keys: Vec<Vec<Key>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let mut bindings = Bindings::empty();
+        for (action, keys) in [
+            (Action::MoveLeft, vec![Key::Left, Key::Char('a')]),
+            (Action::MoveRight, vec![Key::Right, Key::Char('d')]),
+            (Action::SoftDrop, vec![Key::Down, Key::Char('s')]),
+            (Action::HardDrop, vec![Key::Space]),
+            (Action::RotateCw, vec![Key::Char('e'), Key::Char('x')]),
+            (Action::RotateCcw, vec![Key::Up, Key::Char('w'), Key::Char('q'), Key::Char('z')]),
+            (Action::Rotate180, vec![Key::Char('f')]),
+            (Action::Hold, vec![Key::Char('c')]),
+            (Action::Pause, vec![Key::Char('p'), Key::Escape]),
+            (Action::Restart, vec![Key::Char('r')]),
+            (Action::Quit, vec![Key::Char('Q')]),
+        ] {
+            bindings.keys[action.index()] = keys;
+        }
+        bindings
+    }
+}
+
+impl Bindings {
+    /// Returns bindings with no keys bound at all.
+    pub fn empty() -> Bindings {
+        Bindings {
+            keys: vec![Vec::new(); Action::ALL.len()],
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        &self.keys[action.index()]
+    }
+
+    /// Returns the action the key is bound to, if any.
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        Action::ALL.iter().find(|action| self.keys(**action).contains(key)).copied()
+    }
+
+    /// Binds the key to the action, alongside the keys already bound to it. Fails if the key is bound to
+    /// another action, or can't be bound at all: Ctrl-C always quits.
+    pub fn bind(&mut self, action: Action, key: Key) -> Result<(), String> {
+        if key == Key::CtrlC {
+            return Err("Ctrl-C can't be bound, it always quits".to_string());
+        }
+        match self.action(&key) {
+            Some(bound) if bound == action => Ok(()),
+            Some(bound) => Err(format!("{} is already bound to {}", key, bound.label())),
+            None => {
+                self.keys[action.index()].push(key);
+                Ok(())
+            },
+        }
+    }
+
+    /// Removes the key from whatever action it is bound to.
+    pub fn unbind(&mut self, key: &Key) {
+        for keys in &mut self.keys {
+            keys.retain(|bound| bound != key);
+        }
+    }
+
+    /// Removes every key bound to the action.
+    pub fn clear(&mut self, action: Action) {
+        self.keys[action.index()].clear();
+    }
+}
+
+/// Writes the bindings as a key bindings file, which is both TOML and INI: a `[keys]` section with one
+/// `action = ["key", ...]` line per action.
+impl fmt::Display for Bindings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# tetrust key bindings")?;
+        writeln!(f, "#")?;
+        writeln!(f, "# Bind any number of keys to each action, each key as a single character or one of")?;
+        writeln!(f, "# Up, Down, Left, Right, Home, End, PageUp, PageDown, Insert, Delete, Space, Enter, Tab,")?;
+        writeln!(f, "# Backspace, Escape and F1 to F35. The INI style `move_left = Left, a` works too.")?;
+        writeln!(f)?;
+        writeln!(f, "[keys]")?;
+        for action in Action::ALL {
+            let keys: Vec<String> = self.keys(action).iter()
+                .map(|key| format!("\"{}\"", key.to_string().replace('\\', "\\\\").replace('"', "\\\"")))
+                .collect();
+            writeln!(f, "{} = [{}]", action.name(), keys.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bindings {
+    type Err = String;
+
+    /// Parses a key bindings file. A key listed for two actions is an error. Actions left out of the file keep
+    /// those of their default keys the file doesn't use elsewhere.
+    fn from_str(s: &str) -> Result<Bindings, String> {
+        let mut bindings = Bindings::empty();
+        let mut listed = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') || line == "[keys]" {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            if line.starts_with('[') {
+                return Err(error(format!("unknown section '{}'", line)));
+            }
+
+            let (name, value) = line.split_once('=').ok_or_else(|| error(format!("missing '=' in '{}'", line)))?;
+            let action = Action::ALL.iter().find(|action| action.name() == name.trim()).copied()
+                .ok_or_else(|| error(format!("unknown action '{}'", name.trim())))?;
+            bindings.clear(action);
+            listed.push(action);
+            for key in parse_list(value.trim()).map_err(error)? {
+                let key = key.parse().map_err(error)?;
+                bindings.bind(action, key).map_err(error)?;
+            }
+        }
+
+        let defaults = Bindings::default();
+        for action in Action::ALL.iter().filter(|action| !listed.contains(action)) {
+            for key in defaults.keys(*action) {
+                // Keys the file moved to other actions stay there
+                let _ = bindings.bind(*action, *key);
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+/// Splits a list of keys, either a TOML array of strings such as `["Left", "a"]` or bare names separated by
+/// commas such as `Left, a`.
+fn parse_list(value: &str) -> Result<Vec<String>, String> {
+    let list = match value.strip_prefix('[') {
+        Some(list) => list.strip_suffix(']').ok_or_else(|| format!("missing ']' in '{}'", value))?,
+        None => value,
+    };
+
+    let mut items = Vec::new();
+    let mut chars = list.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            None => break,
+            Some(&quote) if quote == '"' || quote == '\'' => {
+                chars.next();
+                let mut item = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') if quote == '"' => item.extend(chars.next()),
+                        Some(c) if c == quote => break,
+                        Some(c) => item.push(c),
+                        None => return Err(format!("unterminated string in '{}'", value)),
+                    }
+                }
+                items.push(item);
+            },
+            Some(_) => {
+                let mut item = String::new();
+                while let Some(c) = chars.next_if(|&c| c != ',') {
+                    item.push(c);
+                }
+                items.push(item.trim().to_string());
+            },
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            None => break,
+            Some(',') => (),
+            Some(c) => return Err(format!("unexpected '{}' in '{}'", c, value)),
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Rotate180, Key::Char('"')).unwrap();
+        bindings.bind(Action::Hold, Key::Char(',')).unwrap();
+        assert_eq!(bindings.to_string().parse(), Ok(bindings));
+    }
+
+    #[test]
+    fn parses_toml_and_ini_lists() {
+        let bindings: Bindings = "[keys]\nmove_left = [\"j\", 'Home']\n; comment\nhold = Tab, F2\nquit =\n"
+            .parse().unwrap();
+        assert_eq!(bindings.keys(Action::MoveLeft), &[Key::Char('j'), Key::Home]);
+        assert_eq!(bindings.keys(Action::Hold), &[Key::Tab, Key::F(2)]);
+        assert_eq!(bindings.keys(Action::Quit), &[]);
+        assert_eq!(bindings.keys(Action::HardDrop), &[Key::Space]);
+        assert_eq!(bindings.keys(Action::MoveRight), &[Key::Right, Key::Char('d')]);
+        assert_eq!(bindings.action(&Key::Char('a')), None);
+
+        // Default keys the file uses elsewhere move over
+        let bindings: Bindings = "hold = a".parse().unwrap();
+        assert_eq!(bindings.keys(Action::Hold), &[Key::Char('a')]);
+        assert_eq!(bindings.keys(Action::MoveLeft), &[Key::Left]);
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let err = "move_left = a\nhold = [\"a\"]".parse::<Bindings>().unwrap_err();
+        assert_eq!(err, "line 2: a is already bound to Move left");
+
+        let mut bindings = Bindings::default();
+        assert!(bindings.bind(Action::Hold, Key::Left).is_err());
+        assert!(bindings.bind(Action::MoveLeft, Key::Left).is_ok());
+        assert!(bindings.bind(Action::Hold, Key::CtrlC).is_err());
+        bindings.unbind(&Key::Left);
+        assert!(bindings.bind(Action::Hold, Key::Left).is_ok());
+        assert_eq!(bindings.action(&Key::Left), Some(Action::Hold));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!("[controls]".parse::<Bindings>().is_err());
+        assert!("jump = Space".parse::<Bindings>().is_err());
+        assert!("hold = [\"c\"".parse::<Bindings>().is_err());
+        assert!("hold = Nope".parse::<Bindings>().is_err());
+        assert!("hold".parse::<Bindings>().is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::piece::Piece;
+use crate::util::{Color, PieceType, Point};
+use std::fmt;
+use std::str::FromStr;
+
+pub const BOARD_WIDTH: u32 = 10;
+pub const BOARD_HEIGHT: u32 = 20;
+pub const HIDDEN_ROWS: u32 = 2;
+
+/// A filled cell of the board.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Cell {
+    /// A block left by a locked piece of the given type.
+    Block(PieceType),
+    /// A block that didn't come from a piece, such as a garbage line.
+    Garbage,
+}
+
+impl Cell {
+    pub fn color(&self) -> Color {
+        match self {
+            Cell::Block(kind) => kind.color(),
+            Cell::Garbage => Color::Grey,
+        }
+    }
+
+    /// Returns the character the cell is written as in the text format: the piece letter or `#`.
+    pub fn to_char(&self) -> char {
+        match self {
+            Cell::Block(kind) => kind.letter(),
+            Cell::Garbage => '#',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Board {
+    cells: [[Option<Cell>; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize],
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Board {
+        Board{
+            cells: [[None; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize]
+        }
+    }
+
+    /// Returns the block at the given column and row, or `None` if the cell is empty.
+    pub fn get(&self, col: u32, row: u32) -> Option<Cell> {
+        self.cells[row as usize][col as usize]
+    }
+
+    pub fn set(&mut self, col: u32, row: u32, cell: Option<Cell>) {
+        self.cells[row as usize][col as usize] = cell;
+    }
+
+    /// Returns true if no cell of the board is filled.
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|row| row.iter().all(|cell| cell.is_none()))
+    }
+
+    /// Returns true if the given position is filled or lies outside the walls and floor of the board.
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        x < 0 || x >= (BOARD_WIDTH as i32) || y < 0 || y >= (BOARD_HEIGHT as i32) ||
+            self.cells[y as usize][x as usize].is_some()
+    }
+
+    pub fn lock_piece(&mut self, piece: &Piece, origin: Point) {
+        piece.each_point(&mut |row, col| {
+            let x = origin.x + col;
+            let y = origin.y + row;
+            self.cells[y as usize][x as usize] = Some(Cell::Block(piece.kind()));
+        });
+    }
+
+    pub fn collision_test(&self, piece: &Piece, origin: Point) -> bool {
+        let mut found = false;
+        piece.each_point(&mut |row, col| {
+            if !found && self.is_occupied(origin.x + col, origin.y + row) {
+                found = true;
+            }
+        });
+
+        found
+    }
+
+    /// Clears the board of any complete lines, shifting down rows to take their place.
+    /// Returns the total number of lines that were cleared.
+    pub fn clear_lines(&mut self) -> u32 {
+        let mut cleared_lines: usize = 0;
+        for row in (0..self.cells.len()).rev() {
+            if (row as i32) - (cleared_lines as i32) < 0 {
+                break;
+            }
+
+            if cleared_lines > 0 {
+                self.cells[row] = self.cells[row - cleared_lines];
+                self.cells[row - cleared_lines] = [None; BOARD_WIDTH as usize];
+            }
+
+            while !self.cells[row].contains(&None) {
+                cleared_lines += 1;
+                self.cells[row] = self.cells[row - cleared_lines];
+                self.cells[row - cleared_lines] = [None; BOARD_WIDTH as usize];
+            }
+        }
+
+        cleared_lines as u32
+    }
+}
+
+/// Writes the board as text, one line per row from the top including the hidden rows: `.` for an empty cell,
+/// the piece letter for a block and `#` for garbage.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.cells {
+            let line: String = row.iter().map(|cell| cell.map_or('.', |cell| cell.to_char())).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    /// Parses the text format written by `Display`. Piece letters may be in either case. Blank lines and
+    /// surrounding whitespace are ignored, and the rows fill the board from the bottom, so a scenario only needs
+    /// to spell out the rows that aren't empty.
+    fn from_str(s: &str) -> Result<Board, String> {
+        let lines: Vec<&str> = s.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+        if lines.len() > BOARD_HEIGHT as usize {
+            return Err(format!("expected at most {} rows, found {}", BOARD_HEIGHT, lines.len()));
+        }
+
+        let mut board = Board::new();
+        let top = BOARD_HEIGHT as usize - lines.len();
+        for (i, line) in lines.iter().enumerate() {
+            if line.chars().count() != BOARD_WIDTH as usize {
+                return Err(format!("expected {} cells in row '{}'", BOARD_WIDTH, line));
+            }
+
+            for (col, c) in line.chars().enumerate() {
+                board.cells[top + i][col] = match c {
+                    '.' => None,
+                    '#' => Some(Cell::Garbage),
+                    c => match PieceType::from_letter(c) {
+                        Some(kind) => Some(Cell::Block(kind)),
+                        None => return Err(format!("unknown cell '{}' in row '{}'", c, line)),
+                    },
+                };
+            }
+        }
+
+        Ok(board)
+    }
+}
@@ -1,9 +1,12 @@
-use crate::util::Color;
+use tetrust::util::Color;
 use std::io::{self, Write};
 
-const ESC: &'static str = "\x1b";
+const ESC: &str = "\x1b";
+/// The kitty keyboard protocol features asked for: unambiguous escape codes (1), press, repeat and release
+/// events (2), escape codes for every key including text (8) and the text the key produced (16).
+const KEYBOARD_FLAGS: u32 = 1 | 2 | 8 | 16;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct Pixel {
     c: char,
     fg_color: Color,
@@ -12,9 +15,17 @@ struct Pixel {
 
 pub struct Display {
     buffer: Vec<Vec<Pixel>>,
+    /// The buffer as last flushed to the terminal. Each render only writes the cells that differ from it.
+    rendered: Option<Vec<Vec<Pixel>>>,
+    /// The foreground and background colors the terminal is currently drawing with, if known.
+    colors: Option<(Color, Color)>,
 }
 
 impl Display {
+    /// Creates a display and switches the terminal to the alternate screen with the cursor hidden, so the
+    /// shell's contents come back untouched once the display is dropped. On the alternate screen it also asks
+    /// for the kitty keyboard protocol with key releases, queries whether the terminal supports it, and turns on
+    /// bracketed paste.
     pub fn new(width: u32, height: u32) -> Display {
         let mut rows = Vec::with_capacity(height as usize);
         for _ in 0..height {
@@ -25,61 +36,69 @@ impl Display {
             rows.push(row);
         }
 
-        Display {
-            buffer: rows
-        }
+        let display = Display {
+            buffer: rows,
+            rendered: None,
+            colors: None,
+        };
+        let keyboard = format!("{}{}", display.esc(&format!(">{}u", KEYBOARD_FLAGS)), display.esc("?u"));
+        display.write(&format!("{}{}{}{}", display.esc("?1049h"), display.esc("?25l"), keyboard, display.esc("?2004h")));
+        display
     }
 
+    /// Writes the buffer to the terminal. Only the cells that changed since the last render are written, with
+    /// the cursor moved and the colors switched only where needed, and everything goes out in a single write.
     pub fn render(&mut self) {
-        self.clear_screen();
-        self.set_cursor_pos(0, 0);
-
-        let mut writer = io::stdout();
-        let mut fg_color = Color::Black;
-        let mut bg_color = Color::Black;
-
-        let mut y = 0;
-
-        for row in &self.buffer {
-            for pixel in row {
-                if pixel.fg_color != fg_color {
-                    fg_color = pixel.fg_color;
-                    self.set_fg_color(pixel.fg_color);
-                 }
-                if pixel.bg_color != bg_color {
-                    bg_color = pixel.bg_color;
-                    self.set_bg_color(pixel.bg_color);
-                 }
-
-                let bytes = [pixel.c as u8];
-                assert!(writer.write_all(&bytes).is_ok());
+        let mut output = String::new();
+        if self.rendered.is_none() {
+            output.push_str(&self.esc("2J"));
+        }
+
+        // Where the terminal's cursor is, as far as we know
+        let mut cursor = None;
+        let mut colors = self.colors;
+
+        for (y, row) in self.buffer.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if self.rendered.as_ref().is_some_and(|rendered| rendered[y][x] == *pixel) {
+                    continue;
+                }
+
+                if cursor != Some((x, y)) {
+                    output.push_str(&self.cursor_pos(x as u32, y as u32));
+                }
+                if colors.map(|(fg_color, _)| fg_color) != Some(pixel.fg_color) {
+                    output.push_str(&self.fg_color(pixel.fg_color));
+                }
+                if colors.map(|(_, bg_color)| bg_color) != Some(pixel.bg_color) {
+                    output.push_str(&self.bg_color(pixel.bg_color));
+                }
+                colors = Some((pixel.fg_color, pixel.bg_color));
+
+                output.push(pixel.c);
+                // Writing the last column leaves the cursor waiting to wrap, so don't rely on where it is
+                cursor = if x + 1 < row.len() { Some((x + 1, y)) } else { None };
             }
-            y += 1;
-            self.set_cursor_pos(0, y);
         }
 
-        assert!(writer.flush().is_ok());
+        if !output.is_empty() {
+            self.write(&output);
+        }
+        self.rendered = Some(self.buffer.clone());
+        self.colors = colors;
     }
 
+    /// Writes text starting at the given position, cutting off whatever runs past the right edge of the screen.
     pub fn set_text(&mut self, text: &str, x: u32, y: u32, fg_color: Color, bg_color: Color) {
         let row = &mut self.buffer[y as usize];
-        let mut i = 0;
 
-        for c in text.chars() {
-            let cell = &mut row[(x + i) as usize];
+        for (cell, c) in row.iter_mut().skip(x as usize).zip(text.chars()) {
             cell.c = c;
             cell.fg_color = fg_color;
             cell.bg_color = bg_color;
-            i += 1;
         }
     }
 
-    pub fn clear_screen(&self) {
-        let mut writer = io::stdout();
-        assert!(writer.write_all(self.esc("2J").as_bytes()).is_ok());
-        assert!(writer.flush().is_ok());
-    }
-
     pub fn clear_buffer(&mut self) {
         for row in 0..self.buffer.len() {
             for col in 0..self.buffer[row].len() {
@@ -90,24 +109,25 @@ impl Display {
         }
     }
 
-    fn set_cursor_pos(&self, x: u32, y: u32) {
+    fn cursor_pos(&self, x: u32, y: u32) -> String {
         // Console positions are 1-based
-        self.print(&self.esc(&format!("{};{}H", y + 1, x + 1)));
+        self.esc(&format!("{};{}H", y + 1, x + 1))
     }
 
     fn esc(&self, text: &str) -> String { format!("{}[{}", ESC, text) }
 
-    fn print(&self, text: &str) {
-        let mut writer = io::stdout();
+    fn write(&self, text: &str) {
+        let mut writer = io::stdout().lock();
         assert!(writer.write_all(text.as_bytes()).is_ok());
+        assert!(writer.flush().is_ok());
     }
 
-    fn set_fg_color(&self, color: Color) {
-        self.print(&self.esc(&format!("38;5;{}m", self.get_color_code(color))));
+    fn fg_color(&self, color: Color) -> String {
+        self.esc(&format!("38;5;{}m", self.get_color_code(color)))
     }
 
-    fn set_bg_color(&self, color: Color) {
-        self.print(&self.esc(&format!("48;5;{}m", self.get_color_code(color))));
+    fn bg_color(&self, color: Color) -> String {
+        self.esc(&format!("48;5;{}m", self.get_color_code(color)))
     }
 
     fn get_color_code(&self, color: Color) -> i32 {
@@ -118,7 +138,17 @@ impl Display {
             Color::Red => 9,
             Color::Blue => 21,
             Color::Orange => 202,
+            Color::Grey => 244,
             Color::Black => 0
         }
     }
 }
+
+impl Drop for Display {
+    /// Resets the colors, shows the cursor again, restores the keyboard protocol, turns bracketed paste off and
+    /// leaves the alternate screen.
+    fn drop(&mut self) {
+        self.write(&format!("{}{}{}{}{}", self.esc("0m"), self.esc("?25h"), self.esc("<u"), self.esc("?2004l"),
+                            self.esc("?1049l")));
+    }
+}
@@ -0,0 +1,70 @@
+//! Finesse: placing each piece with as few inputs as possible.
+
+use crate::board::{Board, BOARD_WIDTH};
+use crate::piece::Piece;
+use crate::srs::{self, FlipKicks};
+use crate::util::{Direction, Point};
+use std::collections::VecDeque;
+
+/// Returns the fewest inputs needed to bring a freshly spawned piece of the same type into the columns and
+/// orientation that `piece` occupies at `position`, assuming an empty board. Taps move one column, holding a
+/// direction until the piece reaches the wall (DAS) counts as a single input, and so does each rotation,
+/// including 180° turns kicked following `flip_kicks`. Orientations that cover the same cells, such as the two
+/// horizontal states of an I piece, are equivalent.
+pub fn minimum_inputs(piece: &Piece, position: Point, flip_kicks: FlipKicks) -> u32 {
+    let board = Board::new();
+    let target = footprint(piece, position);
+
+    let spawn = Piece::new(piece.kind());
+    let origin = Point{ x: ((BOARD_WIDTH - spawn.size()) / 2) as i32, y: 0 };
+
+    let mut visited = vec![(origin, spawn.rotation())];
+    let mut queue = VecDeque::from(vec![(spawn, origin, 0)]);
+    while let Some((piece, origin, inputs)) = queue.pop_front() {
+        if footprint(&piece, origin) == target {
+            return inputs;
+        }
+
+        let mut moves = Vec::new();
+        for dx in [-1, 1] {
+            // Tap once, or hold until the piece hits the wall
+            let tap = Point{ x: origin.x + dx, y: origin.y };
+            if board.collision_test(&piece, tap) {
+                continue;
+            }
+            let mut wall = tap;
+            while !board.collision_test(&piece, Point{ x: wall.x + dx, y: wall.y }) {
+                wall.x += dx;
+            }
+            moves.push((piece.clone(), tap));
+            moves.push((piece.clone(), wall));
+        }
+        for direction in [Direction::Left, Direction::Right, Direction::Flip] {
+            if let Some((rotated, new_origin, _)) = srs::rotate(&board, &piece, origin, direction, flip_kicks) {
+                moves.push((rotated, new_origin));
+            }
+        }
+
+        for (next, next_origin) in moves {
+            let key = (next_origin, next.rotation());
+            if !visited.contains(&key) {
+                visited.push(key);
+                queue.push_back((next, next_origin, inputs + 1));
+            }
+        }
+    }
+
+    // Every placement is reachable on an empty board, but fall back to "no fault possible" just in case.
+    u32::MAX
+}
+
+/// Returns the columns and shape covered by the piece, independent of its height on the board.
+fn footprint(piece: &Piece, origin: Point) -> Vec<(i32, i32)> {
+    let mut cells = Vec::new();
+    piece.each_point(&mut |row, col| cells.push((origin.x + col, origin.y + row)));
+
+    let top = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let mut cells: Vec<(i32, i32)> = cells.into_iter().map(|(x, y)| (x, y - top)).collect();
+    cells.sort();
+    cells
+}
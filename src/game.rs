@@ -0,0 +1,987 @@
+use crate::board::{Board, BOARD_WIDTH, HIDDEN_ROWS};
+use crate::finesse;
+use crate::mode::Mode;
+use crate::piece::Piece;
+use crate::piece_bag::{PieceBag, MAX_PREVIEW};
+use crate::randomizer::RandomizerKind;
+use crate::scoring::{Scoring, ScoringModel};
+use crate::srs::{self, FlipKicks, Rotation};
+use crate::util::{from_debug_name, Direction, PieceType, Point};
+use std::time::Duration;
+
+/// How long a piece may rest on the stack before it locks.
+pub const LOCK_DELAY: Duration = Duration::from_millis(500);
+/// How many times moving or rotating a resting piece may restart its lock delay.
+pub const MAX_LOCK_RESETS: u32 = 15;
+/// How long a horizontal move must be held before it starts repeating (delayed auto shift).
+pub const DAS: Duration = Duration::from_millis(167);
+/// How often a held horizontal move repeats once the DAS has passed (auto repeat rate).
+pub const ARR: Duration = Duration::from_millis(33);
+/// How many times faster than gravity a piece falls while soft drop is held.
+pub const SOFT_DROP_FACTOR: u32 = 20;
+
+/// The reference frame length that gravity speeds are expressed against.
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+/// The fastest gravity, in rows per frame ("20G" drops a piece through the whole board instantly).
+const MAX_GRAVITY: f64 = 20.0;
+
+/// Returns how long a piece takes to fall one row at the given level, following the guideline curve
+/// `(0.8 - (level - 1) * 0.007) ^ (level - 1)` seconds per row, capped at 20G.
+pub fn gravity_interval(level: u32) -> Duration {
+    let level = level as f64;
+    let seconds = (0.8 - (level - 1.0) * 0.007).max(0.0).powf(level - 1.0);
+    Duration::from_secs_f64(seconds.max(FRAME.as_secs_f64() / MAX_GRAVITY))
+}
+
+/// The rules a game is played with.
+#[derive(Debug, Copy, Clone)]
+pub struct Config {
+    /// Seeds the piece generator; two games with the same config and inputs play out identically.
+    pub seed: u64,
+    pub mode: Mode,
+    pub lock_delay: Duration,
+    pub max_lock_resets: u32,
+    pub scoring: Scoring,
+    pub randomizer: RandomizerKind,
+    /// How many upcoming pieces to preview, from 1 to `MAX_PREVIEW`.
+    pub next_count: usize,
+    pub das: Duration,
+    /// Zero shifts a piece straight to the wall once the DAS has passed.
+    pub arr: Duration,
+    pub soft_drop_factor: u32,
+    /// The kicks tried when a piece turns 180°.
+    pub flip_kicks: FlipKicks,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            seed: 0,
+            mode: Mode::Marathon,
+            lock_delay: LOCK_DELAY,
+            max_lock_resets: MAX_LOCK_RESETS,
+            scoring: Scoring::Guideline,
+            randomizer: RandomizerKind::SevenBag,
+            next_count: MAX_PREVIEW,
+            das: DAS,
+            arr: ARR,
+            soft_drop_factor: SOFT_DROP_FACTOR,
+            flip_kicks: FlipKicks::SrsPlus,
+        }
+    }
+}
+
+/// The actions a player can take.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Input {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+}
+
+impl Input {
+    pub const ALL: [Input; 8] = [
+        Input::MoveLeft,
+        Input::MoveRight,
+        Input::SoftDrop,
+        Input::HardDrop,
+        Input::RotateCw,
+        Input::RotateCcw,
+        Input::Rotate180,
+        Input::Hold,
+    ];
+}
+
+from_debug_name!(Input, "input");
+
+/// The kinds of T-spin recognised by the 3-corner rule.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum TSpin {
+    Mini,
+    Full,
+}
+
+/// Describes a lock that cleared lines or was a T-spin, so frontends can announce it.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ClearEvent {
+    pub lines: u32,
+    pub t_spin: Option<TSpin>,
+    pub points: u32,
+    pub back_to_back: bool,
+    /// The combo this clear extended, 0 if it started a new one.
+    pub combo: u32,
+    /// True if the clear emptied the whole board.
+    pub perfect_clear: bool,
+    /// The game time at which the piece locked.
+    pub time: Duration,
+}
+
+impl ClearEvent {
+    /// Returns the name of the clear, such as "Tetris" or "T-Spin Mini Single".
+    pub fn description(&self) -> String {
+        let lines = match self.lines {
+            0 => "",
+            1 => "Single",
+            2 => "Double",
+            3 => "Triple",
+            _ => "Tetris",
+        };
+        let t_spin = match self.t_spin {
+            Some(TSpin::Full) => "T-Spin ",
+            Some(TSpin::Mini) => "T-Spin Mini ",
+            None => "",
+        };
+        format!("{}{}", t_spin, lines).trim_end().to_string()
+    }
+}
+
+/// Why the player lost, following the guideline's definitions.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum GameOver {
+    /// A piece locked entirely inside the hidden rows above the visible playfield.
+    LockOut,
+    /// A new piece had no room to spawn.
+    BlockOut,
+    /// The stack was pushed up through the top of the board. Only incoming garbage can do this, which the
+    /// engine doesn't send yet.
+    TopOut,
+}
+
+impl GameOver {
+    pub const ALL: [GameOver; 3] = [GameOver::LockOut, GameOver::BlockOut, GameOver::TopOut];
+
+    /// Returns a short explanation of the reason, at most 45 characters long.
+    pub fn description(&self) -> &str {
+        match self {
+            GameOver::LockOut => "Lock Out: a piece locked above the playfield.",
+            GameOver::BlockOut => "Block Out: no room for the next piece.",
+            GameOver::TopOut => "Top Out: the stack rose above the playfield.",
+        }
+    }
+}
+
+from_debug_name!(GameOver, "game over reason");
+
+pub struct Game {
+    config: Config,
+    scoring: Box<dyn ScoringModel>,
+    board: Board,
+    piece_bag: PieceBag,
+    piece: Piece,
+    piece_position: Point,
+    held_piece: Option<Piece>,
+    hold_used: bool,
+    /// The direction and the index of the kick used if the last successful action on the current piece was a
+    /// rotation.
+    last_rotation_kick: Option<(Direction, usize)>,
+    last_clear: Option<ClearEvent>,
+    time: Duration,
+    gravity_timer: Duration,
+    lock_timer: Option<Duration>,
+    lock_resets: u32,
+    lowest_row: i32,
+    score: u32,
+    /// The points earned with each piece type, indexed like `PieceType::ALL`.
+    piece_scores: [u32; 7],
+    level: u32,
+    total_lines: u32,
+    /// The game time at which every tenth line was cleared.
+    splits: Vec<Duration>,
+    pieces_placed: u32,
+    /// The number of moves and rotations used on the current piece.
+    piece_inputs: u32,
+    soft_dropped: bool,
+    /// The horizontal moves held down, the most recently pressed last. Only the last one repeats.
+    held_moves: Vec<Input>,
+    /// How long the repeating move has been held.
+    shift_timer: Duration,
+    soft_drop_held: bool,
+    finesse_faults: u32,
+    game_over: bool,
+    game_over_reason: Option<GameOver>,
+    completed: bool,
+}
+
+impl Game {
+    pub fn new(config: Config) -> Game {
+        Game::with_scoring(config, config.scoring.model())
+    }
+
+    /// Creates a game that starts on the given board instead of an empty one, for example to set up a scenario
+    /// parsed from text.
+    pub fn with_board(config: Config, board: Board) -> Game {
+        let mut game = Game::new(config);
+        game.board = board;
+        if !game.place_new_piece() {
+            game.lose(GameOver::BlockOut);
+        }
+        game
+    }
+
+    /// Creates a game that scores with a custom model instead of the one named in the config.
+    pub fn with_scoring(config: Config, scoring: Box<dyn ScoringModel>) -> Game {
+        let mut piece_bag = PieceBag::new(config.randomizer.build(config.seed));
+        let piece = piece_bag.pop();
+
+        let mut game = Game {
+            config,
+            scoring,
+            board: Board::new(),
+            piece_bag,
+            piece,
+            piece_position: Point{ x: 0, y: 0 },
+            held_piece: None,
+            hold_used: false,
+            last_rotation_kick: None,
+            last_clear: None,
+            time: Duration::ZERO,
+            gravity_timer: Duration::ZERO,
+            lock_timer: None,
+            lock_resets: 0,
+            lowest_row: 0,
+            level: 1,
+            score: 0,
+            piece_scores: [0; 7],
+            total_lines: 0,
+            splits: Vec::new(),
+            pieces_placed: 0,
+            piece_inputs: 0,
+            soft_dropped: false,
+            held_moves: Vec::new(),
+            shift_timer: Duration::ZERO,
+            soft_drop_held: false,
+            finesse_faults: 0,
+            game_over: false,
+            game_over_reason: None,
+            completed: false,
+        };
+
+        game.place_new_piece();
+        game
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the currently falling piece.
+    pub fn piece(&self) -> &Piece {
+        &self.piece
+    }
+
+    /// Returns the board position of the top-left corner of the current piece's grid.
+    pub fn piece_position(&self) -> Point {
+        self.piece_position
+    }
+
+    /// Returns a copy of the next piece in the queue.
+    pub fn next_piece(&self) -> Piece {
+        self.piece_bag.peek()
+    }
+
+    /// Returns copies of the upcoming pieces, as many as the config's `next_count`.
+    pub fn next_pieces(&self) -> Vec<Piece> {
+        self.piece_bag.peek_n(self.config.next_count.clamp(1, MAX_PREVIEW))
+    }
+
+    pub fn held_piece(&self) -> Option<&Piece> {
+        self.held_piece.as_ref()
+    }
+
+    /// Returns true if the current piece may still be swapped into the hold slot.
+    pub fn can_hold(&self) -> bool {
+        !self.hold_used
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn total_lines(&self) -> u32 {
+        self.total_lines
+    }
+
+    /// Returns true once the game has ended, either because the player topped out or because they reached the
+    /// mode's goal.
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    /// Returns why the player lost, or `None` while the game is running or if it ended by reaching the mode's
+    /// goal.
+    pub fn game_over_reason(&self) -> Option<GameOver> {
+        self.game_over_reason
+    }
+
+    /// Returns true if the game ended because the player reached the mode's goal.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Returns the game time at which every tenth line was cleared.
+    pub fn splits(&self) -> &[Duration] {
+        &self.splits
+    }
+
+    /// Returns the points earned while playing pieces of the given type, including drop points.
+    pub fn piece_score(&self, kind: PieceType) -> u32 {
+        self.piece_scores[kind.index()]
+    }
+
+    pub fn pieces_placed(&self) -> u32 {
+        self.pieces_placed
+    }
+
+    /// Returns how many pieces were placed with more moves and rotations than necessary.
+    pub fn finesse_faults(&self) -> u32 {
+        self.finesse_faults
+    }
+
+    /// Returns the total game time that has passed through `step`.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Returns the most recent lock that cleared lines or scored a T-spin.
+    pub fn last_clear(&self) -> Option<&ClearEvent> {
+        self.last_clear.as_ref()
+    }
+
+    /// Starts holding an input. It takes effect straight away like `apply`, then held horizontal moves repeat
+    /// after the DAS and held soft drop speeds gravity up by the soft drop factor until `release` is called.
+    /// Other inputs don't repeat.
+    pub fn press(&mut self, input: Input) {
+        if self.game_over {
+            return;
+        }
+
+        self.repeat(input, Duration::ZERO);
+        self.apply(input);
+    }
+
+    /// Starts holding an input that was already applied, without applying it again. This is how a tap turns into
+    /// a held input once its key starts repeating, on terminals that don't report key presses and releases.
+    /// `held_for` is how long ago the key went down, which a held horizontal move counts towards its DAS.
+    pub fn repeat(&mut self, input: Input, held_for: Duration) {
+        if self.game_over {
+            return;
+        }
+
+        match input {
+            Input::MoveLeft | Input::MoveRight => {
+                self.held_moves.retain(|held| *held != input);
+                self.held_moves.push(input);
+                self.shift_timer = held_for;
+            },
+            Input::SoftDrop => self.soft_drop_held = true,
+            _ => (),
+        }
+    }
+
+    /// Stops holding an input. If another horizontal move is still held, it takes over and charges its DAS
+    /// from the start.
+    pub fn release(&mut self, input: Input) {
+        match input {
+            Input::MoveLeft | Input::MoveRight => {
+                if self.held_moves.last() == Some(&input) {
+                    self.shift_timer = Duration::ZERO;
+                }
+                self.held_moves.retain(|held| *held != input);
+            },
+            Input::SoftDrop => self.soft_drop_held = false,
+            _ => (),
+        }
+    }
+
+    /// Applies a player input. Inputs are ignored once the game is over.
+    pub fn apply(&mut self, input: Input) {
+        if self.game_over {
+            return;
+        }
+
+        match input {
+            Input::MoveLeft | Input::MoveRight | Input::RotateCw | Input::RotateCcw | Input::Rotate180 => {
+                self.piece_inputs += 1
+            },
+            Input::SoftDrop => self.soft_dropped = true,
+            _ => (),
+        }
+
+        let moved = match input {
+            Input::MoveLeft => self.move_piece(-1, 0),
+            Input::MoveRight => self.move_piece(1, 0),
+            Input::RotateCcw => self.rotate_piece(Direction::Left),
+            Input::RotateCw => self.rotate_piece(Direction::Right),
+            Input::Rotate180 => self.rotate_piece(Direction::Flip),
+            Input::SoftDrop => {
+                let row = self.piece_position.y;
+                self.advance_game();
+                if self.piece_position.y > row {
+                    let points = self.scoring.soft_drop(1);
+                    self.add_score(points);
+                }
+                false
+            },
+            Input::HardDrop => {
+                self.drop_piece();
+                false
+            },
+            Input::Hold => {
+                self.hold_piece();
+                false
+            },
+        };
+
+        if moved {
+            self.reset_lock_delay();
+        }
+    }
+
+    /// Advances the game clock by `dt`, repeating held moves, applying gravity and locking the current piece
+    /// once its lock delay runs out.
+    pub fn step(&mut self, dt: Duration) {
+        if self.game_over {
+            return;
+        }
+        self.time += dt;
+
+        if let Some(time_limit) = self.config.mode.time_limit() {
+            if self.time >= time_limit {
+                self.time = time_limit;
+                self.completed = true;
+                self.game_over = true;
+                return;
+            }
+        }
+
+        self.auto_shift(dt);
+
+        match self.lock_timer {
+            Some(remaining) if remaining > dt => self.lock_timer = Some(remaining - dt),
+            Some(_) => {
+                self.lock_timeout();
+            },
+            None => {
+                // Gravity only accumulates while the piece is falling
+                self.gravity_timer += dt;
+                let mut interval = gravity_interval(self.level);
+                if self.soft_drop_held {
+                    interval = (interval / self.config.soft_drop_factor.max(1)).max(Duration::from_nanos(1));
+                }
+                while self.gravity_timer >= interval {
+                    self.gravity_timer -= interval;
+                    let row = self.piece_position.y;
+                    if !self.advance_game() {
+                        return;
+                    }
+                    if self.soft_drop_held && self.piece_position.y > row {
+                        let points = self.scoring.soft_drop(1);
+                        self.add_score(points);
+                    }
+                    if self.lock_timer.is_some() {
+                        self.gravity_timer = Duration::ZERO;
+                        break;
+                    }
+                }
+            },
+        }
+    }
+
+    /// Repeats the held horizontal move: once it has been held for the DAS, the piece shifts once every ARR, or
+    /// straight to the wall with an ARR of zero. Repeated shifts don't count as inputs for finesse, just like
+    /// holding a direction to the wall counts as a single input there.
+    fn auto_shift(&mut self, dt: Duration) {
+        let dx = match self.held_moves.last() {
+            Some(Input::MoveLeft) => -1,
+            Some(_) => 1,
+            None => return,
+        };
+
+        let (das, arr) = (self.config.das, self.config.arr);
+        // The number of shifts due after holding for the given time, the first one as the DAS runs out
+        let shifts = |held: Duration| match held.checked_sub(das) {
+            Some(charged) => charged.as_nanos() / arr.as_nanos() + 1,
+            None => 0,
+        };
+        let held = self.shift_timer + dt;
+        let due = if held < das {
+            0
+        } else if arr.is_zero() {
+            // Keep the piece against the wall, including new pieces spawning while the move is still held
+            BOARD_WIDTH as u128
+        } else {
+            shifts(held) - shifts(self.shift_timer)
+        };
+        self.shift_timer = held;
+
+        let mut moved = false;
+        for _ in 0..due.min(BOARD_WIDTH as u128) {
+            if !self.move_piece(dx, 0) {
+                break;
+            }
+            moved = true;
+        }
+        if moved {
+            self.reset_lock_delay();
+        }
+    }
+
+    /// Returns the new position of the current piece if it were to be dropped.
+    pub fn find_dropped_position(&self) -> Point {
+        let mut origin = self.piece_position;
+        while !self.board.collision_test(&self.piece, origin) {
+            origin.y += 1;
+        }
+        origin.y -= 1;
+        origin
+    }
+
+    /// Moves the current piece in the specified direction. Returns true if the piece could be moved and
+    /// didn't collide.
+    fn move_piece(&mut self, x: i32, y: i32) -> bool {
+        let new_position = Point{
+            x: self.piece_position.x + x,
+            y: self.piece_position.y + y,
+        };
+        if self.board.collision_test(&self.piece, new_position) {
+            false
+        } else {
+            self.piece_position = new_position;
+            self.last_rotation_kick = None;
+            true
+        }
+    }
+
+    /// Rotates the current piece in the specified direction, trying each of the SRS wall kicks, or the configured
+    /// 180° kicks, in turn until one fits. Returns true if the piece could be rotated without any collisions.
+    fn rotate_piece(&mut self, direction: Direction) -> bool {
+        match srs::rotate(&self.board, &self.piece, self.piece_position, direction, self.config.flip_kicks) {
+            Some((piece, origin, kick)) => {
+                self.piece = piece;
+                self.piece_position = origin;
+                self.last_rotation_kick = Some((direction, kick));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Positions the current piece at the top of the board. Returns true if the piece can be placed without
+    /// any collisions.
+    fn place_new_piece(&mut self) -> bool {
+        let origin = Point{
+            x: ((BOARD_WIDTH - self.piece.size()) / 2) as i32,
+            y: 0,
+        };
+        if self.board.collision_test(&self.piece, origin) {
+            false
+        } else {
+            self.piece_position = origin;
+            self.last_rotation_kick = None;
+            self.piece_inputs = 0;
+            self.soft_dropped = false;
+            self.gravity_timer = Duration::ZERO;
+            self.lock_timer = None;
+            self.lock_resets = 0;
+            self.lowest_row = origin.y;
+            true
+        }
+    }
+
+    /// Returns true if the current piece is resting on the stack or the floor.
+    fn is_grounded(&self) -> bool {
+        let below = Point{ x: self.piece_position.x, y: self.piece_position.y + 1 };
+        self.board.collision_test(&self.piece, below)
+    }
+
+    /// Restarts the lock delay after the player successfully moved or rotated the current piece, as long as
+    /// the piece has resets left. Reaching a new lowest row gives the piece a fresh set of resets. A piece moved
+    /// off a ledge falls again even without resets left, and then locks as soon as it touches down.
+    fn reset_lock_delay(&mut self) {
+        if self.piece_position.y > self.lowest_row {
+            self.lowest_row = self.piece_position.y;
+            self.lock_resets = 0;
+        }
+
+        if self.lock_timer.is_none() {
+            return;
+        }
+        let grounded = self.is_grounded();
+        if self.lock_resets < self.config.max_lock_resets {
+            self.lock_resets += 1;
+            if grounded {
+                self.lock_timer = Some(self.config.lock_delay);
+            }
+        }
+        if !grounded {
+            self.lock_timer = None;
+        }
+    }
+
+    /// Advances the game by moving the current piece down one step. If the piece cannot move down, the lock
+    /// delay starts; once the piece has used up all of its resets it locks as soon as it touches down. Returns
+    /// true if the game could be advanced, false if the player has lost.
+    fn advance_game(&mut self) -> bool {
+        if self.move_piece(0, 1) {
+            if self.piece_position.y > self.lowest_row {
+                self.lowest_row = self.piece_position.y;
+                self.lock_resets = 0;
+            }
+            self.lock_timer = None;
+            return true;
+        }
+
+        if self.lock_timer.is_none() {
+            if self.lock_resets >= self.config.max_lock_resets {
+                return self.lock_current_piece();
+            }
+            self.lock_timer = Some(self.config.lock_delay);
+        }
+
+        true
+    }
+
+    /// Called when the lock delay expires. Locks the current piece if it is still resting on something.
+    /// Returns false if the player has lost.
+    fn lock_timeout(&mut self) -> bool {
+        self.lock_timer = None;
+        if self.is_grounded() {
+            self.lock_current_piece()
+        } else {
+            true
+        }
+    }
+
+    /// Checks whether the current piece is in a T-spin position using the 3-corner rule: the piece must be a T
+    /// whose last action was a rotation, with at least three of the four cells diagonal to its center
+    /// occupied. It is a full T-spin if both corners on the pointing side are occupied, or if a quarter turn
+    /// needed the last SRS kick, and a mini T-spin otherwise.
+    fn detect_t_spin(&self) -> Option<TSpin> {
+        let (direction, kick) = self.last_rotation_kick?;
+        if self.piece.kind() != PieceType::T {
+            return None;
+        }
+
+        let x = self.piece_position.x + 1;
+        let y = self.piece_position.y + 1;
+        let top_left = self.board.is_occupied(x - 1, y - 1);
+        let top_right = self.board.is_occupied(x + 1, y - 1);
+        let bottom_left = self.board.is_occupied(x - 1, y + 1);
+        let bottom_right = self.board.is_occupied(x + 1, y + 1);
+
+        let corners = [top_left, top_right, bottom_left, bottom_right].iter().filter(|&&c| c).count();
+        if corners < 3 {
+            return None;
+        }
+
+        let (front_a, front_b) = match self.piece.rotation() {
+            Rotation::Spawn => (top_left, top_right),
+            Rotation::Right => (top_right, bottom_right),
+            Rotation::Flip => (bottom_left, bottom_right),
+            Rotation::Left => (top_left, bottom_left),
+        };
+        if (front_a && front_b) || (direction != Direction::Flip && kick == 4) {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
+        }
+    }
+
+    /// Locks the current piece into the board, clears lines and sets up the next piece. Returns false if the
+    /// game ended, either because the next piece could not be placed or because the mode's goal was reached.
+    fn lock_current_piece(&mut self) -> bool {
+        // Soft dropped pieces may have been tucked under overhangs, which finesse doesn't account for
+        let minimum_inputs = finesse::minimum_inputs(&self.piece, self.piece_position, self.config.flip_kicks);
+        if !self.soft_dropped && self.piece_inputs > minimum_inputs {
+            self.finesse_faults += 1;
+        }
+        self.pieces_placed += 1;
+
+        let t_spin = self.detect_t_spin();
+        self.board.lock_piece(&self.piece, self.piece_position);
+
+        let origin = self.piece_position;
+        let mut locked_out = true;
+        self.piece.each_point(&mut |row, _| {
+            if origin.y + row >= HIDDEN_ROWS as i32 {
+                locked_out = false;
+            }
+        });
+
+        let lines_cleared = self.board.clear_lines();
+        let perfect_clear = lines_cleared > 0 && self.board.is_empty();
+
+        // Update the score based on the number of lines cleared and how the piece got there
+        let award = self.scoring.lock(lines_cleared, t_spin, perfect_clear, self.level);
+        self.add_score(award.points);
+        if lines_cleared > 0 || t_spin.is_some() {
+            self.last_clear = Some(ClearEvent {
+                lines: lines_cleared,
+                t_spin,
+                points: award.points,
+                back_to_back: award.back_to_back,
+                combo: award.combo,
+                perfect_clear,
+                time: self.time,
+            });
+        }
+
+        if lines_cleared > 0 {
+            if (self.total_lines + lines_cleared) / 10 > self.total_lines / 10 {
+                self.splits.push(self.time);
+            }
+            self.total_lines += lines_cleared;
+
+            if self.total_lines >= self.level * 10 {
+                // Level up every 10 lines cleared
+                self.level += 1;
+            }
+        }
+
+        if let Some(goal) = self.config.mode.line_goal() {
+            if self.total_lines >= goal {
+                self.completed = true;
+                self.game_over = true;
+                return false;
+            }
+        }
+
+        if locked_out {
+            self.lose(GameOver::LockOut);
+            return false;
+        }
+
+        self.piece = self.piece_bag.pop();
+        self.hold_used = false;
+
+        if !self.place_new_piece() {
+            self.lose(GameOver::BlockOut);
+            return false;
+        }
+
+        true
+    }
+
+    fn lose(&mut self, reason: GameOver) {
+        self.game_over = true;
+        self.game_over_reason = Some(reason);
+    }
+
+    /// Drops the current piece to the lowest spot on the board where it fits without collisions and
+    /// locks it immediately.
+    fn drop_piece(&mut self) -> bool {
+        let mut cells = 0;
+        while self.move_piece(0, 1) {
+            cells += 1;
+        }
+        let points = self.scoring.hard_drop(cells);
+        self.add_score(points);
+        self.lock_current_piece()
+    }
+
+    /// Adds points to the score, crediting them to the type of the current piece.
+    fn add_score(&mut self, points: u32) {
+        self.score += points;
+        self.piece_scores[self.piece.kind().index()] += points;
+    }
+
+    /// Swaps the current piece with the held one, or with the next piece in the bag if nothing is held yet.
+    /// Only one hold is allowed per locked piece. Returns true if the swap happened.
+    fn hold_piece(&mut self) -> bool {
+        if self.hold_used {
+            return false;
+        }
+
+        let next_piece = match self.held_piece.take() {
+            Some(piece) => piece,
+            None => self.piece_bag.pop(),
+        };
+        let held_piece = std::mem::replace(&mut self.piece, next_piece);
+        self.held_piece = Some(Piece::new(held_piece.kind()));
+        self.hold_used = true;
+
+        if !self.place_new_piece() {
+            self.lose(GameOver::BlockOut);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BOARD_HEIGHT;
+    use crate::scoring::Award;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A five row ledge under the left six columns.
+    const LEDGE: &str = "
+        ######....
+        ######....
+        ######....
+        ######....
+        ######....
+    ";
+
+    /// Returns a game on the ledge board whose current piece is an O resting on the ledge, with its lock delay
+    /// running.
+    fn o_on_ledge(lock_delay: Duration, max_lock_resets: u32) -> Game {
+        let config = Config { lock_delay, max_lock_resets, ..Config::default() };
+        let mut game = Game::with_board(config, LEDGE.parse().unwrap());
+        game.piece = Piece::new(PieceType::O);
+        assert!(game.place_new_piece());
+        drop_to_lock_delay(&mut game);
+        game
+    }
+
+    /// Soft drops the current piece until it touches down and its lock delay starts.
+    fn drop_to_lock_delay(game: &mut Game) {
+        for _ in 0..BOARD_HEIGHT {
+            game.apply(Input::SoftDrop);
+            if game.lock_timer.is_some() {
+                return;
+            }
+        }
+        panic!("the piece never touched down");
+    }
+
+    /// Records the lines and perfect clear flag of every lock.
+    struct LockLog(Rc<RefCell<Vec<(u32, bool)>>>);
+
+    impl ScoringModel for LockLog {
+        fn lock(&mut self, lines: u32, _t_spin: Option<TSpin>, perfect_clear: bool, _level: u32) -> Award {
+            self.0.borrow_mut().push((lines, perfect_clear));
+            Award { points: 0, back_to_back: false, combo: 0 }
+        }
+
+        fn soft_drop(&mut self, _cells: u32) -> u32 {
+            0
+        }
+
+        fn hard_drop(&mut self, _cells: u32) -> u32 {
+            0
+        }
+    }
+
+    /// Drops an I piece upright into the right column of the board, returning the locks the scoring model saw.
+    fn drop_i_on(board: &str) -> Vec<(u32, bool)> {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut game = Game::with_scoring(Config::default(), Box::new(LockLog(log.clone())));
+        game.board = board.parse().unwrap();
+        game.piece = Piece::new(PieceType::I);
+        assert!(game.place_new_piece());
+        game.apply(Input::RotateCw);
+        for _ in 0..4 {
+            game.apply(Input::MoveRight);
+        }
+        game.apply(Input::HardDrop);
+        let locks = log.borrow().clone();
+        locks
+    }
+
+    #[test]
+    fn only_clears_that_empty_the_board_are_perfect() {
+        let well = "#########.\n".repeat(4);
+        assert_eq!(drop_i_on(&well), [(4, true)]);
+        assert_eq!(drop_i_on(&format!("#.........\n{}", well)), [(4, false)]);
+        assert_eq!(drop_i_on(""), [(0, false)]);
+    }
+
+    #[test]
+    fn locking_inside_the_hidden_rows_is_a_lock_out() {
+        let mut game = Game::with_board(Config::default(), "#########.\n".repeat(18).parse().unwrap());
+        game.apply(Input::HardDrop);
+        assert_eq!(game.game_over_reason(), Some(GameOver::LockOut));
+    }
+
+    #[test]
+    fn no_room_to_spawn_is_a_block_out() {
+        let game = Game::with_board(Config::default(), "#########.\n".repeat(20).parse().unwrap());
+        assert_eq!(game.game_over_reason(), Some(GameOver::BlockOut));
+
+        // Only an O fits between the top rows, so holding it to bring out a T blocks out
+        let top = format!("####..####\n####..####\n{}", "..........\n".repeat(18));
+        let mut game = Game::new(Config::default());
+        game.board = top.parse().unwrap();
+        game.piece = Piece::new(PieceType::O);
+        assert!(game.place_new_piece());
+        assert!(!game.is_game_over());
+        game.held_piece = Some(Piece::new(PieceType::T));
+        game.apply(Input::Hold);
+        assert_eq!(game.game_over_reason(), Some(GameOver::BlockOut));
+    }
+
+    fn step_frames(game: &mut Game, frames: u32) {
+        for _ in 0..frames {
+            game.step(FRAME);
+        }
+    }
+
+    #[test]
+    fn lock_delay_resets_are_capped() {
+        let mut game = o_on_ledge(FRAME * 10, 3);
+        for input in [Input::MoveLeft, Input::MoveRight, Input::MoveLeft] {
+            step_frames(&mut game, 5);
+            game.apply(input);
+        }
+        step_frames(&mut game, 9);
+        assert_eq!(game.pieces_placed(), 0);
+
+        // Out of resets, so moving again no longer buys time
+        game.apply(Input::MoveRight);
+        step_frames(&mut game, 1);
+        assert_eq!(game.pieces_placed(), 1);
+    }
+
+    #[test]
+    fn new_lowest_row_gives_fresh_resets() {
+        let mut game = o_on_ledge(FRAME * 10, 2);
+        game.apply(Input::MoveLeft);
+        game.apply(Input::MoveRight);
+        assert_eq!(game.lock_resets, 2);
+
+        // Off the ledge and down to the floor
+        game.apply(Input::MoveRight);
+        game.apply(Input::MoveRight);
+        drop_to_lock_delay(&mut game);
+        assert_eq!(game.lock_resets, 0);
+        step_frames(&mut game, 5);
+        game.apply(Input::MoveRight);
+        step_frames(&mut game, 9);
+        assert_eq!(game.pieces_placed(), 0);
+    }
+
+    #[test]
+    fn piece_moved_off_a_ledge_falls_without_resets_left() {
+        let mut game = o_on_ledge(FRAME * 50, 2);
+        game.apply(Input::MoveLeft);
+        game.apply(Input::MoveRight);
+        game.apply(Input::MoveRight);
+        assert!(game.lock_timer.is_some());
+
+        let y = game.piece_position.y;
+        game.apply(Input::MoveRight);
+        assert_eq!(game.lock_timer, None);
+
+        // One row per second at level 1, well before the lock delay would have run out and let gravity restart
+        step_frames(&mut game, 61);
+        assert_eq!(game.piece_position.y, y + 1);
+        assert_eq!(game.pieces_placed(), 0);
+    }
+}
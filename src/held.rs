@@ -0,0 +1,166 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tetrust::replay::Recorder;
+use tetrust::Input;
+
+/// Set once the terminal confirms it speaks the kitty keyboard protocol, which reports key releases.
+pub static RELEASES_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Without release events, how long after a tap the same key arriving again may be its first repeat. Needs to
+/// cover the system's key repeat delay, which is up to about 660 ms by default.
+const MAX_REPEAT_DELAY: Duration = Duration::from_millis(700);
+/// Without release events, the gap under which the same key arriving again is the system repeating it, and how
+/// long a held key may go without repeating before it counts as released. Needs to cover the gap between key
+/// repeats, which is 30 to 90 ms on most systems.
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Tracks which of the inputs that repeat while held (moving and soft dropping) are held down, and presses and
+/// releases them on the game, which takes care of DAS, ARR and the soft drop factor.
+///
+/// Terminals speaking the kitty keyboard protocol report key releases. Other terminals only send a held key
+/// again after the system's key repeat delay, then again and again in quick succession. There every arrival of
+/// a key is a tap, as the first repeat can't be told from a second tap, and the key counts as held once it
+/// arrives twice within `RELEASE_TIMEOUT` until it stops repeating for as long.
+pub struct HeldInputs {
+    /// The inputs held down and when their key was last seen.
+    held: Vec<(Input, Instant)>,
+    /// The last input tapped, to recognise its key repeating.
+    last_tap: Option<Tap>,
+}
+
+/// An input applied as a tap while its key may still be down.
+#[derive(Copy, Clone)]
+struct Tap {
+    input: Input,
+    /// When the key went down if it's repeating: the arrival before the last one when that may have been the
+    /// first repeat, or else the last one.
+    down: Instant,
+    /// When the input last arrived.
+    seen: Instant,
+}
+
+impl HeldInputs {
+    pub fn new() -> HeldInputs {
+        HeldInputs {
+            held: Vec::new(),
+            last_tap: None,
+        }
+    }
+
+    /// Handles a key press, or a key repeat, mapped to the given input.
+    pub fn press(&mut self, input: Input, recorder: &mut Recorder) {
+        self.press_at(input, Instant::now(), recorder);
+    }
+
+    fn press_at(&mut self, input: Input, now: Instant, recorder: &mut Recorder) {
+        if !matches!(input, Input::MoveLeft | Input::MoveRight | Input::SoftDrop) {
+            recorder.apply(input);
+            return;
+        }
+
+        if let Some((_, seen)) = self.held.iter_mut().find(|(held, _)| *held == input) {
+            *seen = now;
+            return;
+        }
+
+        if RELEASES_REPORTED.load(Ordering::Relaxed) {
+            recorder.press(input);
+            self.held.push((input, now));
+            return;
+        }
+
+        match self.last_tap.filter(|tap| tap.input == input) {
+            Some(tap) if now - tap.seen < RELEASE_TIMEOUT => {
+                // The earlier arrivals already moved the piece, so only start holding it
+                recorder.repeat(input, now - tap.down);
+                self.held.push((input, now));
+                self.last_tap = None;
+            },
+            Some(tap) if now - tap.seen < MAX_REPEAT_DELAY => {
+                recorder.apply(input);
+                self.last_tap = Some(Tap { input, down: tap.seen, seen: now });
+            },
+            _ => {
+                recorder.apply(input);
+                self.last_tap = Some(Tap { input, down: now, seen: now });
+            },
+        }
+    }
+
+    /// Handles a key release reported by the terminal.
+    pub fn release(&mut self, input: Input, recorder: &mut Recorder) {
+        if let Some(i) = self.held.iter().position(|(held, _)| *held == input) {
+            self.held.remove(i);
+            recorder.release(input);
+        }
+    }
+
+    /// Releases the inputs whose key stopped repeating, unless the terminal reports releases itself.
+    pub fn expire(&mut self, recorder: &mut Recorder) {
+        if RELEASES_REPORTED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let now = Instant::now();
+        self.held.retain(|&(input, seen)| {
+            let expired = now - seen >= RELEASE_TIMEOUT;
+            if expired {
+                recorder.release(input);
+            }
+            !expired
+        });
+    }
+
+    /// Releases every held input, for example when the game is paused and releases would go unnoticed.
+    pub fn release_all(&mut self, recorder: &mut Recorder) {
+        for (input, _) in self.held.drain(..) {
+            recorder.release(input);
+        }
+        self.last_tap = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetrust::replay::InputEvent;
+    use tetrust::Config;
+
+    /// Feeds the given inputs to `HeldInputs`, each at a time in milliseconds, and returns the recorded events.
+    fn arrivals(inputs: &[(Input, u64)]) -> Vec<InputEvent> {
+        let start = Instant::now();
+        let mut held = HeldInputs::new();
+        let mut recorder = Recorder::new(Config::default());
+        for &(input, at) in inputs {
+            held.press_at(input, start + Duration::from_millis(at), &mut recorder);
+        }
+        recorder.replay().inputs.iter().map(|input| input.event).collect()
+    }
+
+    #[test]
+    fn double_taps_move_twice() {
+        let events = arrivals(&[(Input::MoveLeft, 0), (Input::MoveLeft, 150)]);
+        assert_eq!(events, [InputEvent::Tap, InputEvent::Tap]);
+    }
+
+    #[test]
+    fn quick_arrivals_hold_from_when_the_key_went_down() {
+        let events = arrivals(&[(Input::MoveLeft, 0), (Input::MoveLeft, 500), (Input::MoveLeft, 530),
+            (Input::MoveLeft, 560)]);
+        assert_eq!(events, [InputEvent::Tap, InputEvent::Tap, InputEvent::Repeat(Duration::from_millis(530))]);
+    }
+
+    #[test]
+    fn holding_after_a_double_tap_counts_from_the_second_tap() {
+        let events = arrivals(&[(Input::MoveRight, 0), (Input::MoveRight, 150), (Input::MoveRight, 650),
+            (Input::MoveRight, 680)]);
+        assert_eq!(events[3], InputEvent::Repeat(Duration::from_millis(530)));
+    }
+
+    #[test]
+    fn other_inputs_never_hold() {
+        let events = arrivals(&[(Input::RotateCw, 0), (Input::RotateCw, 30), (Input::MoveLeft, 60),
+            (Input::MoveRight, 90)]);
+        assert_eq!(events, [InputEvent::Tap; 4]);
+    }
+}
@@ -0,0 +1,265 @@
+//! High score tables, kept separately for every game mode.
+
+use crate::game::{Game, GameOver};
+use crate::mode::Mode;
+use crate::util::parse_number;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+const HEADER: &str = "tetrust-highscores 1";
+
+/// The number of results kept for each mode.
+pub const MAX_ENTRIES: usize = 10;
+/// The longest player name accepted on a new record.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+/// The result of a finished game.
+#[derive(Debug, PartialEq, Clone)]
+pub struct HighScore {
+    pub mode: Mode,
+    pub name: String,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub time: Duration,
+    /// When the game was played, in seconds since the Unix epoch.
+    pub date: u64,
+    /// Why the game ended, or `None` if it ended by reaching the mode's goal.
+    pub reason: Option<GameOver>,
+}
+
+impl HighScore {
+    /// Returns the result of a finished game with an empty player name, or `None` if the game has nothing
+    /// worth ranking: it is still running, it is a sprint that was not completed, or it scored no points.
+    pub fn from_game(game: &Game, date: u64) -> Option<HighScore> {
+        let mode = game.config().mode;
+        let ranked = match mode {
+            Mode::Sprint { .. } => game.is_completed(),
+            _ => game.score() > 0,
+        };
+        if !game.is_game_over() || !ranked {
+            return None;
+        }
+
+        Some(HighScore {
+            mode,
+            name: String::new(),
+            score: game.score(),
+            lines: game.total_lines(),
+            level: game.level(),
+            time: game.time(),
+            date,
+            reason: game.game_over_reason(),
+        })
+    }
+
+    /// Returns true if this result ranks strictly above the other one. Sprints rank by time, every other mode
+    /// ranks by score.
+    pub fn beats(&self, other: &HighScore) -> bool {
+        match self.mode {
+            Mode::Sprint { .. } => self.time < other.time,
+            _ => self.score > other.score,
+        }
+    }
+
+    /// Formats the date the game was played on as `YYYY-MM-DD` (UTC).
+    pub fn date_string(&self) -> String {
+        // Converts days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+        let days = (self.date / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// The best results of every mode, each mode's entries ordered from best to worst.
+#[derive(Debug, Default, Clone)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn new() -> HighScores {
+        HighScores { entries: Vec::new() }
+    }
+
+    /// Returns the table of the given mode, best result first.
+    pub fn table(&self, mode: Mode) -> Vec<&HighScore> {
+        self.entries.iter().filter(|entry| entry.mode == mode).collect()
+    }
+
+    /// Returns true if the result would make it into its mode's table.
+    pub fn is_record(&self, entry: &HighScore) -> bool {
+        self.rank(entry) < MAX_ENTRIES
+    }
+
+    /// Adds the result to its mode's table, dropping the worst entry if the table is full. Returns the
+    /// position the result took in the table, or `None` if it did not make it in.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.rank(&entry);
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+
+        let mode = entry.mode;
+        let index = self.entries.iter().position(|other| other.mode == mode && entry.beats(other))
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+
+        if let Some(last) = self.entries.iter().rposition(|other| other.mode == mode) {
+            if self.table(mode).len() > MAX_ENTRIES {
+                self.entries.remove(last);
+            }
+        }
+        Some(rank)
+    }
+
+    /// Returns the position the result would take in its table. Ties go to the older result.
+    fn rank(&self, entry: &HighScore) -> usize {
+        self.table(entry.mode).iter().take_while(|other| !entry.beats(other)).count()
+    }
+}
+
+/// Writes the tables as a plain text file: a header, then one tab separated line per result holding the mode,
+/// score, lines, level, time in milliseconds, date, game over reason (`-` if the mode's goal was reached) and
+/// player name.
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                entry.mode,
+                entry.score,
+                entry.lines,
+                entry.level,
+                entry.time.as_millis(),
+                entry.date,
+                entry.reason.map_or("-".to_string(), |reason| format!("{:?}", reason)),
+                entry.name,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HighScores {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<HighScores, String> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a tetrust high score file".to_string());
+        }
+
+        let mut high_scores = HighScores::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.splitn(8, '\t').collect();
+            if fields.len() != 8 {
+                return Err(format!("invalid high score '{}'", line));
+            }
+
+            // The file is written best first, so pushing keeps every table in order
+            high_scores.entries.push(HighScore {
+                mode: fields[0].parse()?,
+                score: parse_number(fields[1])?,
+                lines: parse_number(fields[2])?,
+                level: parse_number(fields[3])?,
+                time: Duration::from_millis(parse_number(fields[4])?),
+                date: parse_number(fields[5])?,
+                reason: match fields[6] {
+                    "-" => None,
+                    reason => Some(reason.parse()?),
+                },
+                name: fields[7].to_string(),
+            });
+        }
+
+        Ok(high_scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(mode: Mode, name: &str, score: u32, seconds: u64) -> HighScore {
+        HighScore {
+            mode,
+            name: name.to_string(),
+            score,
+            lines: 40,
+            level: 5,
+            time: Duration::from_secs(seconds),
+            date: 1_700_000_000,
+            reason: if let Mode::Sprint { .. } = mode { None } else { Some(GameOver::TopOut) },
+        }
+    }
+
+    fn names(high_scores: &HighScores, mode: Mode) -> Vec<&str> {
+        high_scores.table(mode).iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sprints_rank_by_time_and_other_modes_by_score() {
+        let sprint = Mode::Sprint { lines: 40 };
+        let mut high_scores = HighScores::new();
+        assert_eq!(high_scores.insert(entry(Mode::Marathon, "b", 200, 90)), Some(0));
+        assert_eq!(high_scores.insert(entry(Mode::Marathon, "a", 300, 120)), Some(0));
+        assert_eq!(high_scores.insert(entry(Mode::Marathon, "c", 100, 30)), Some(2));
+        assert_eq!(high_scores.insert(entry(sprint, "slow", 900, 95)), Some(0));
+        assert_eq!(high_scores.insert(entry(sprint, "fast", 100, 60)), Some(0));
+
+        assert_eq!(names(&high_scores, Mode::Marathon), ["a", "b", "c"]);
+        assert_eq!(names(&high_scores, sprint), ["fast", "slow"]);
+    }
+
+    #[test]
+    fn ties_go_to_the_older_result() {
+        let mut high_scores = HighScores::new();
+        high_scores.insert(entry(Mode::Marathon, "first", 100, 60));
+        assert_eq!(high_scores.insert(entry(Mode::Marathon, "second", 100, 30)), Some(1));
+        assert_eq!(names(&high_scores, Mode::Marathon), ["first", "second"]);
+    }
+
+    #[test]
+    fn tables_keep_the_best_entries_of_each_mode() {
+        let ultra = Mode::Ultra { time_limit: Duration::from_secs(120) };
+        let mut high_scores = HighScores::new();
+        high_scores.insert(entry(ultra, "ultra", 50, 120));
+        for score in 1..=MAX_ENTRIES as u32 + 2 {
+            high_scores.insert(entry(Mode::Marathon, &score.to_string(), score * 100, 60));
+        }
+
+        let table = high_scores.table(Mode::Marathon);
+        assert_eq!(table.len(), MAX_ENTRIES);
+        assert_eq!(table[0].score, 1200);
+        assert_eq!(table[MAX_ENTRIES - 1].score, 300);
+        assert!(!high_scores.is_record(&entry(Mode::Marathon, "low", 300, 60)));
+        assert_eq!(high_scores.insert(entry(Mode::Marathon, "low", 250, 60)), None);
+        assert_eq!(names(&high_scores, ultra), ["ultra"]);
+    }
+
+    #[test]
+    fn saved_tables_read_back() {
+        let mut high_scores = HighScores::new();
+        high_scores.insert(entry(Mode::Marathon, "Player One", 1234, 75));
+        high_scores.insert(entry(Mode::Marathon, "b", 99, 20));
+        high_scores.insert(entry(Mode::Sprint { lines: 20 }, "c", 0, 42));
+
+        let read: HighScores = high_scores.to_string().parse().unwrap();
+        for mode in [Mode::Marathon, Mode::Sprint { lines: 20 }] {
+            assert_eq!(read.table(mode), high_scores.table(mode));
+        }
+        assert!("tetrust-highscores 1\nMarathon\t12".parse::<HighScores>().is_err());
+        assert!("".parse::<HighScores>().is_err());
+    }
+}
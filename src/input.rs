@@ -0,0 +1,646 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long to wait for the rest of an escape sequence before taking an ESC byte as the Escape key.
+pub const ESC_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// The longest escape sequence kept waiting for its final byte. Longer ones are dropped as garbage.
+const MAX_SEQUENCE_LENGTH: usize = 64;
+
+const PASTE_START: &str = "200";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// A function key, from F1 up.
+    F(u8),
+    Space,
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    CtrlC,
+    Char(char),
+}
+
+impl Key {
+    /// The keys with a name rather than a character, apart from the function keys.
+    const NAMED: [Key; 15] = [
+        Key::Up,
+        Key::Down,
+        Key::Left,
+        Key::Right,
+        Key::Home,
+        Key::End,
+        Key::PageUp,
+        Key::PageDown,
+        Key::Insert,
+        Key::Delete,
+        Key::Space,
+        Key::Enter,
+        Key::Tab,
+        Key::Backspace,
+        Key::Escape,
+    ];
+}
+
+/// Names the key as a single character, or by its name such as `Left`, `F5` or `Space`.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::F(n) => write!(f, "F{}", n),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::CtrlC => write!(f, "Ctrl-C"),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parses a key named as by its `Display` implementation. Names are case-insensitive, characters aren't.
+    fn from_str(s: &str) -> Result<Key, String> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return match c {
+                ' ' => Ok(Key::Space),
+                c if c.is_control() => Err(format!("unknown key {:?}", s)),
+                c => Ok(Key::Char(c)),
+            };
+        }
+
+        let function = s.strip_prefix(['F', 'f']).and_then(|n| n.parse().ok()).filter(|n| (1..=35).contains(n));
+        if let Some(n) = function {
+            return Ok(Key::F(n));
+        }
+        Key::NAMED.iter().find(|key| format!("{:?}", key).eq_ignore_ascii_case(s)).copied()
+            .ok_or_else(|| format!("unknown key '{}'", s))
+    }
+}
+
+/// The modifier keys held with a key. Plain text typed with Shift reports the shifted character instead.
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    const NONE: Modifiers = Modifiers { shift: false, alt: false, ctrl: false };
+    const CTRL: Modifiers = Modifiers { shift: false, alt: false, ctrl: true };
+
+    /// Decodes the modifier parameter of an escape sequence, which is 1 plus a bit mask.
+    fn from_param(param: u32) -> Modifiers {
+        let bits = param.saturating_sub(1);
+        Modifiers {
+            shift: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            ctrl: bits & 4 != 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    /// A key was pressed, or repeated while held down on terminals that don't tell the two apart.
+    Press(Key, Modifiers),
+    /// A key repeated while held down. Only terminals speaking the kitty keyboard protocol report these.
+    Repeat(Key, Modifiers),
+    /// A key was let go. Only terminals speaking the kitty keyboard protocol report these.
+    Release(Key, Modifiers),
+    /// Text pasted while bracketed paste is on.
+    Paste(String),
+    /// The terminal's answer to the query for the kitty keyboard protocol flags it has enabled.
+    KeyboardFlags(u32),
+}
+
+/// The result of decoding the start of the input.
+enum Decoded {
+    /// The first bytes decoded into an event, or into nothing worth reporting.
+    Complete(Option<Event>, usize),
+    /// The bytes could be the start of a longer sequence.
+    Incomplete,
+}
+
+/// Decodes terminal input into key events. Bytes arrive in arbitrary chunks, so anything cut off at the end
+/// of a chunk is kept until the rest arrives: escape sequences (CSI and SS3, including the kitty keyboard
+/// protocol's), multibyte UTF-8 characters and bracketed pastes.
+///
+/// An ESC byte on its own is either the Escape key or the start of a sequence, and only time tells them apart.
+/// When nothing follows within `ESC_TIMEOUT`, call `timeout` to take whatever is waiting as typed.
+pub struct Decoder {
+    /// Bytes waiting for the rest of their sequence.
+    pending: Vec<u8>,
+    /// The text received so far inside a bracketed paste.
+    paste: Option<Vec<u8>>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            pending: Vec::new(),
+            paste: None,
+        }
+    }
+
+    /// Returns true if bytes are waiting for the rest of an escape sequence or character.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Decodes the bytes, following on from the previous ones, and returns the complete events.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.pending.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while start < self.pending.len() {
+            let decoded = match &mut self.paste {
+                Some(paste) => decode_paste(&self.pending[start..], paste),
+                None => decode(&self.pending[start..]),
+            };
+            match decoded {
+                Decoded::Complete(event, len) => {
+                    match event {
+                        Some(Event::Paste(_)) if self.paste.is_none() => self.paste = Some(Vec::new()),
+                        Some(Event::Paste(_)) => {
+                            let text = self.paste.take().unwrap_or_default();
+                            events.push(Event::Paste(String::from_utf8_lossy(&text).to_string()));
+                        },
+                        Some(event) => events.push(event),
+                        None => (),
+                    }
+                    start += len;
+                },
+                Decoded::Incomplete => break,
+            }
+        }
+        self.pending.drain(..start);
+        events
+    }
+
+    /// Takes the bytes still waiting as complete once no more arrived for a while: a lone ESC is the Escape
+    /// key, and anything else cut short is dropped. A bracketed paste keeps waiting for its end.
+    pub fn timeout(&mut self) -> Vec<Event> {
+        if self.paste.is_some() {
+            return Vec::new();
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        match pending.as_slice() {
+            [0x1b] => vec![Event::Press(Key::Escape, Modifiers::NONE)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Decodes the event at the start of the input. The start of a bracketed paste decodes as an empty paste.
+fn decode(bytes: &[u8]) -> Decoded {
+    if bytes[0] != 0x1b {
+        return decode_char(bytes);
+    }
+
+    match bytes.get(1) {
+        None => Decoded::Incomplete,
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => match bytes.get(2) {
+            None => Decoded::Incomplete,
+            Some(&c) => Decoded::Complete(ss3_key(c).map(|key| Event::Press(key, Modifiers::NONE)), 3),
+        },
+        // Two escapes in a row are the Escape key pressed twice, or with Alt
+        Some(0x1b) => Decoded::Complete(Some(Event::Press(Key::Escape, Modifiers::NONE)), 1),
+        // ESC in front of a key means it was pressed with Alt
+        Some(_) => match decode_char(&bytes[1..]) {
+            Decoded::Complete(Some(Event::Press(key, modifiers)), len) => {
+                Decoded::Complete(Some(Event::Press(key, Modifiers { alt: true, ..modifiers })), len + 1)
+            },
+            decoded => decoded,
+        },
+    }
+}
+
+/// Decodes a character or control byte at the start of the input.
+fn decode_char(bytes: &[u8]) -> Decoded {
+    let press = |key, modifiers| Decoded::Complete(Some(Event::Press(key, modifiers)), 1);
+    match bytes[0] {
+        b' ' => press(Key::Space, Modifiers::NONE),
+        b'\r' | b'\n' => press(Key::Enter, Modifiers::NONE),
+        b'\t' => press(Key::Tab, Modifiers::NONE),
+        0x7f | 0x08 => press(Key::Backspace, Modifiers::NONE),
+        0x03 => press(Key::CtrlC, Modifiers::CTRL),
+        0x00 => press(Key::Space, Modifiers::CTRL),
+        // The other control bytes are Ctrl with a letter, or with one of the symbols after the letters
+        c @ 0x01..=0x1a => press(Key::Char((c + b'`') as char), Modifiers::CTRL),
+        c @ 0x1c..=0x1f => press(Key::Char((c + b'@') as char), Modifiers::CTRL),
+        c if c.is_ascii() => press(Key::Char(c as char), Modifiers::NONE),
+        c => {
+            let len = match c {
+                0xc2..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf4 => 4,
+                // Not the start of a UTF-8 character
+                _ => return Decoded::Complete(None, 1),
+            };
+            if bytes.len() < len {
+                // Give up early on bytes that can't continue the character
+                return match bytes[1..].iter().all(|&b| b & 0xc0 == 0x80) {
+                    true => Decoded::Incomplete,
+                    false => Decoded::Complete(None, 1),
+                };
+            }
+            match std::str::from_utf8(&bytes[..len]) {
+                Ok(text) => {
+                    let c = text.chars().next().expect("a decoded character");
+                    Decoded::Complete(Some(Event::Press(Key::Char(c), Modifiers::NONE)), len)
+                },
+                Err(_) => Decoded::Complete(None, 1),
+            }
+        },
+    }
+}
+
+/// Decodes a control sequence, `ESC [` followed by parameter bytes and a final byte.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    for (i, &b) in bytes.iter().enumerate().skip(2) {
+        match b {
+            // Parameter and intermediate bytes
+            0x20..=0x3f => (),
+            0x40..=0x7e => {
+                let params = String::from_utf8_lossy(&bytes[2..i]);
+                return Decoded::Complete(csi_event(&params, b as char), i + 1);
+            },
+            // Anything else breaks the sequence off, and gets decoded on its own
+            _ => return Decoded::Complete(None, i),
+        }
+    }
+
+    if bytes.len() > MAX_SEQUENCE_LENGTH {
+        Decoded::Complete(None, bytes.len())
+    } else {
+        Decoded::Incomplete
+    }
+}
+
+/// Returns the event for the control sequence `ESC [ <params> <last>`. Keys are sent as
+/// `<code>;<modifiers>:<event><last>`, where each part is optional, and the kitty keyboard protocol adds the
+/// text the key produced as a third parameter of `u` sequences.
+fn csi_event(params: &str, last: char) -> Option<Event> {
+    if let Some(flags) = params.strip_prefix('?') {
+        return match last {
+            'u' => flags.parse().ok().map(Event::KeyboardFlags),
+            _ => None,
+        };
+    }
+    if params.starts_with(['<', '=', '>']) {
+        return None;
+    }
+    if last == '~' && params == PASTE_START {
+        return Some(Event::Paste(String::new()));
+    }
+
+    let mut fields = params.split(';');
+    let code = fields.next().and_then(|field| field.split(':').next()?.parse::<u32>().ok());
+    let (modifiers, event) = match fields.next() {
+        Some(field) => {
+            let mut parts = field.split(':').map(|part| part.parse::<u32>().unwrap_or(1));
+            (Modifiers::from_param(parts.next().unwrap_or(1)), parts.next().unwrap_or(1))
+        },
+        None => (Modifiers::NONE, 1),
+    };
+    let text = fields.next()
+        .and_then(|field| field.split(':').next()?.parse().ok())
+        .and_then(char::from_u32);
+
+    let key = match last {
+        '~' => tilde_key(code?)?,
+        'u' => kitty_key(code?, text, modifiers)?,
+        'Z' => Key::Tab,
+        // Cursor position reports also end in R, but never start at row 1 column 1
+        'R' if code.unwrap_or(1) != 1 => return None,
+        c => ss3_key(c as u8)?,
+    };
+    let modifiers = Modifiers { shift: modifiers.shift || last == 'Z', ..modifiers };
+
+    match event {
+        2 => Some(Event::Repeat(key, modifiers)),
+        3 => Some(Event::Release(key, modifiers)),
+        _ => Some(Event::Press(key, modifiers)),
+    }
+}
+
+/// Returns the key sent as `ESC O <c>`, which is also the final byte of the same key's CSI sequence.
+fn ss3_key(c: u8) -> Option<Key> {
+    match c {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        b'M' => Some(Key::Enter),
+        b'P'..=b'S' => Some(Key::F(c - b'P' + 1)),
+        _ => None,
+    }
+}
+
+/// Returns the key sent as `ESC [ <code> ~`.
+fn tilde_key(code: u32) -> Option<Key> {
+    match code {
+        1 | 7 => Some(Key::Home),
+        2 => Some(Key::Insert),
+        3 => Some(Key::Delete),
+        4 | 8 => Some(Key::End),
+        5 => Some(Key::PageUp),
+        6 => Some(Key::PageDown),
+        11..=15 => Some(Key::F((code - 10) as u8)),
+        17..=21 => Some(Key::F((code - 11) as u8)),
+        23..=26 => Some(Key::F((code - 12) as u8)),
+        28 | 29 => Some(Key::F((code - 13) as u8)),
+        31..=34 => Some(Key::F((code - 14) as u8)),
+        _ => None,
+    }
+}
+
+/// Returns the key sent by the kitty keyboard protocol as `ESC [ <code> u`. Codes are Unicode code points,
+/// with the keys that have none, such as the keypad and modifier keys, in the private use area.
+fn kitty_key(code: u32, text: Option<char>, modifiers: Modifiers) -> Option<Key> {
+    let key = match code {
+        9 => Key::Tab,
+        13 | 57414 => Key::Enter,
+        27 => Key::Escape,
+        32 => Key::Space,
+        8 | 127 => Key::Backspace,
+        99 if modifiers.ctrl => Key::CtrlC,
+        57376..=57398 => Key::F((code - 57376 + 13) as u8),
+        57417 => Key::Left,
+        57418 => Key::Right,
+        57419 => Key::Up,
+        57420 => Key::Down,
+        57421 => Key::PageUp,
+        57422 => Key::PageDown,
+        57423 => Key::Home,
+        57424 => Key::End,
+        57425 => Key::Insert,
+        57426 => Key::Delete,
+        // The keypad's digits and operators, which type their text
+        57399..=57413 | 57415 => Key::Char(text.or_else(|| "0123456789./*-+".chars().nth((code - 57399) as usize))?),
+        // Modifier keys on their own and the other keys without text
+        57344..=63743 => return None,
+        // Without the text the key produced, the code is the key without Shift
+        _ => Key::Char(text.or_else(|| char::from_u32(code).map(|c| match modifiers.shift {
+            true => c.to_ascii_uppercase(),
+            false => c,
+        }))?),
+    };
+    Some(key)
+}
+
+/// Collects the text of a bracketed paste up to its end marker, which decodes as the finished paste.
+fn decode_paste(bytes: &[u8], paste: &mut Vec<u8>) -> Decoded {
+    if let Some(end) = bytes.windows(PASTE_END.len()).position(|window| window == PASTE_END) {
+        paste.extend_from_slice(&bytes[..end]);
+        return Decoded::Complete(Some(Event::Paste(String::new())), end + PASTE_END.len());
+    }
+
+    // Keep the bytes that could be the start of the end marker
+    let keep = (1..PASTE_END.len()).rev()
+        .find(|&len| bytes.ends_with(&PASTE_END[..len]))
+        .unwrap_or(0);
+    let len = bytes.len() - keep;
+    paste.extend_from_slice(&bytes[..len]);
+    if len == 0 {
+        Decoded::Incomplete
+    } else {
+        Decoded::Complete(None, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key: Key) -> Event {
+        Event::Press(key, Modifiers::NONE)
+    }
+
+    fn decode_all(bytes: &[u8]) -> Vec<Event> {
+        let mut decoder = Decoder::new();
+        let mut events = decoder.feed(bytes);
+        events.extend(decoder.timeout());
+        events
+    }
+
+    #[test]
+    fn plain_keys() {
+        assert_eq!(decode_all(b"a Z\r\x7f\t"), vec![
+            press(Key::Char('a')),
+            press(Key::Space),
+            press(Key::Char('Z')),
+            press(Key::Enter),
+            press(Key::Backspace),
+            press(Key::Tab),
+        ]);
+    }
+
+    #[test]
+    fn control_bytes() {
+        assert_eq!(decode_all(b"\x03\x01\x1a"), vec![
+            Event::Press(Key::CtrlC, Modifiers::CTRL),
+            Event::Press(Key::Char('a'), Modifiers::CTRL),
+            Event::Press(Key::Char('z'), Modifiers::CTRL),
+        ]);
+    }
+
+    #[test]
+    fn cursor_keys_in_both_modes() {
+        assert_eq!(decode_all(b"\x1b[A\x1b[B\x1bOC\x1bOD"), vec![
+            press(Key::Up),
+            press(Key::Down),
+            press(Key::Right),
+            press(Key::Left),
+        ]);
+    }
+
+    #[test]
+    fn navigation_and_function_keys() {
+        assert_eq!(decode_all(b"\x1b[H\x1b[4~\x1b[5~\x1b[6~\x1b[2~\x1b[3~\x1bOP\x1b[15~\x1b[24~"), vec![
+            press(Key::Home),
+            press(Key::End),
+            press(Key::PageUp),
+            press(Key::PageDown),
+            press(Key::Insert),
+            press(Key::Delete),
+            press(Key::F(1)),
+            press(Key::F(5)),
+            press(Key::F(12)),
+        ]);
+    }
+
+    #[test]
+    fn modifiers() {
+        let ctrl_shift = Modifiers { shift: true, alt: false, ctrl: true };
+        assert_eq!(decode_all(b"\x1b[1;5C\x1b[1;6A\x1b[Z\x1b[3;3~"), vec![
+            Event::Press(Key::Right, Modifiers::CTRL),
+            Event::Press(Key::Up, ctrl_shift),
+            Event::Press(Key::Tab, Modifiers { shift: true, ..Modifiers::NONE }),
+            Event::Press(Key::Delete, Modifiers { alt: true, ..Modifiers::NONE }),
+        ]);
+    }
+
+    #[test]
+    fn alt_prefix() {
+        assert_eq!(decode_all(b"\x1bx\x1b\r"), vec![
+            Event::Press(Key::Char('x'), Modifiers { alt: true, ..Modifiers::NONE }),
+            Event::Press(Key::Enter, Modifiers { alt: true, ..Modifiers::NONE }),
+        ]);
+    }
+
+    #[test]
+    fn lone_escape_waits_for_the_timeout() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(b"\x1b"), vec![]);
+        assert!(decoder.is_pending());
+        assert_eq!(decoder.timeout(), vec![press(Key::Escape)]);
+        assert!(!decoder.is_pending());
+        assert_eq!(decoder.feed(b"q"), vec![press(Key::Char('q'))]);
+    }
+
+    #[test]
+    fn double_escape() {
+        assert_eq!(decode_all(b"\x1b\x1b"), vec![press(Key::Escape), press(Key::Escape)]);
+    }
+
+    #[test]
+    fn sequences_split_across_reads() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(b"\x1b"), vec![]);
+        assert_eq!(decoder.feed(b"["), vec![]);
+        assert_eq!(decoder.feed(b"1;5"), vec![]);
+        assert_eq!(decoder.feed(b"Da"), vec![Event::Press(Key::Left, Modifiers::CTRL), press(Key::Char('a'))]);
+        assert!(!decoder.is_pending());
+    }
+
+    #[test]
+    fn utf8_characters() {
+        assert_eq!(decode_all("é€🦀".as_bytes()), vec![
+            press(Key::Char('é')),
+            press(Key::Char('€')),
+            press(Key::Char('🦀')),
+        ]);
+    }
+
+    #[test]
+    fn utf8_split_across_reads() {
+        let bytes = "€".as_bytes();
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(&bytes[..1]), vec![]);
+        assert_eq!(decoder.feed(&bytes[1..2]), vec![]);
+        assert_eq!(decoder.feed(&bytes[2..]), vec![press(Key::Char('€'))]);
+    }
+
+    #[test]
+    fn invalid_utf8_is_skipped() {
+        assert_eq!(decode_all(b"\xff\x80a\xe2(b"), vec![
+            press(Key::Char('a')),
+            press(Key::Char('(')),
+            press(Key::Char('b')),
+        ]);
+    }
+
+    #[test]
+    fn unknown_and_broken_sequences_are_skipped() {
+        assert_eq!(decode_all(b"\x1b[5n\x1b[<0;3;4M\x1b[12xa\x1b[1\x1bOA"), vec![
+            press(Key::Char('a')),
+            press(Key::Up),
+        ]);
+    }
+
+    #[test]
+    fn cursor_position_report_is_not_f3() {
+        assert_eq!(decode_all(b"\x1b[12;40R\x1b[1;2R"), vec![
+            Event::Press(Key::F(3), Modifiers { shift: true, ..Modifiers::NONE }),
+        ]);
+    }
+
+    #[test]
+    fn bracketed_paste() {
+        assert_eq!(decode_all(b"a\x1b[200~hello \x1b[A world\x1b[201~b"), vec![
+            press(Key::Char('a')),
+            Event::Paste("hello \x1b[A world".to_string()),
+            press(Key::Char('b')),
+        ]);
+    }
+
+    #[test]
+    fn bracketed_paste_split_across_reads() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(b"\x1b[200~pas"), vec![]);
+        assert_eq!(decoder.feed(b"te\x1b[20"), vec![]);
+        assert_eq!(decoder.timeout(), vec![]);
+        assert_eq!(decoder.feed(b"1~"), vec![Event::Paste("paste".to_string())]);
+    }
+
+    #[test]
+    fn kitty_keys() {
+        assert_eq!(decode_all(b"\x1b[97u\x1b[97;2;65u\x1b[13u\x1b[32u\x1b[127u\x1b[27u\x1b[99;5u"), vec![
+            press(Key::Char('a')),
+            Event::Press(Key::Char('A'), Modifiers { shift: true, ..Modifiers::NONE }),
+            press(Key::Enter),
+            press(Key::Space),
+            press(Key::Backspace),
+            press(Key::Escape),
+            Event::Press(Key::CtrlC, Modifiers::CTRL),
+        ]);
+    }
+
+    #[test]
+    fn kitty_repeat_and_release_events() {
+        assert_eq!(decode_all(b"\x1b[1;1:2D\x1b[1;1:3D\x1b[97;1:3u\x1b[97;2:3u\x1b[115;5:2u"), vec![
+            Event::Repeat(Key::Left, Modifiers::NONE),
+            Event::Release(Key::Left, Modifiers::NONE),
+            Event::Release(Key::Char('a'), Modifiers::NONE),
+            Event::Release(Key::Char('A'), Modifiers { shift: true, ..Modifiers::NONE }),
+            Event::Repeat(Key::Char('s'), Modifiers::CTRL),
+        ]);
+    }
+
+    #[test]
+    fn kitty_functional_keys() {
+        assert_eq!(decode_all(b"\x1b[57441u\x1b[57419u\x1b[57400u\x1b[57414u\x1b[57376u"), vec![
+            press(Key::Up),
+            press(Key::Char('1')),
+            press(Key::Enter),
+            press(Key::F(13)),
+        ]);
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for key in Key::NAMED.iter().copied().chain([Key::F(1), Key::F(12), Key::Char('a'), Key::Char('é')]) {
+            assert_eq!(key.to_string().parse(), Ok(key));
+        }
+        assert_eq!("pageup".parse(), Ok(Key::PageUp));
+        assert_eq!(" ".parse(), Ok(Key::Space));
+        assert_eq!("A".parse(), Ok(Key::Char('A')));
+        assert!("F99".parse::<Key>().is_err());
+        assert!("Nope".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn kitty_flags_answer() {
+        assert_eq!(decode_all(b"\x1b[?27u\x1b[?1;2c"), vec![Event::KeyboardFlags(27)]);
+    }
+}
@@ -0,0 +1,22 @@
+//! The Tetris engine behind tetrust.
+//!
+//! The engine is deterministic and does no I/O: a frontend creates a [`Game`] from a [`Config`], feeds it
+//! player [`Input`]s with [`Game::apply`] (or holds them down with [`Game::press`] and [`Game::release`]),
+//! advances time with [`Game::step`] and reads the state back through the getters to draw it however it likes.
+
+pub mod board;
+pub mod finesse;
+pub mod game;
+pub mod high_score;
+pub mod mode;
+pub mod piece;
+pub mod piece_bag;
+pub mod randomizer;
+pub mod render;
+pub mod replay;
+pub mod scoring;
+pub mod srs;
+pub mod text;
+pub mod util;
+
+pub use game::{Config, Game, Input};
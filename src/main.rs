@@ -1,712 +1,909 @@
-mod util;
+mod ansi;
+mod bindings;
 mod display;
+mod held;
+mod input;
+mod menu;
+mod storage;
 mod terminal;
 
+use ansi::AnsiRenderer;
+use bindings::{Action, Bindings};
 use display::Display;
+use held::{HeldInputs, RELEASES_REPORTED};
+use input::{Decoder, Event, Key, ESC_TIMEOUT};
+use menu::{Menu, LOGO_WIDTH};
+use std::io;
+use std::path::PathBuf;
 use std::thread;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::time::Duration;
-use util::*;
-use rand::seq::SliceRandom;
-
-
-const BOARD_WIDTH: u32 = 10;
-const BOARD_HEIGHT: u32 = 20;
-const HIDDEN_ROWS: u32 = 2;
-
-#[derive(PartialEq)]
-enum Key {
-    Up,
-    Down,
-    Left,
-    Right,
-    Space,
-    CtrlC,
-    Char(char),
-}
+use tetrust::board::{BOARD_HEIGHT, BOARD_WIDTH};
+use tetrust::game::FRAME;
+use tetrust::high_score::{HighScore, HighScores, MAX_ENTRIES, MAX_NAME_LENGTH};
+use tetrust::mode::{Mode, SPRINT_LINES, ULTRA_TIME};
+use tetrust::piece_bag::MAX_PREVIEW;
+use tetrust::randomizer::RandomizerKind;
+use tetrust::render::{self, format_time};
+use tetrust::replay::{Playback, Recorder, Replay};
+use tetrust::scoring::Scoring;
+use tetrust::srs::FlipKicks;
+use tetrust::util::{Color, PieceType};
+use tetrust::{Config, Game, Input};
+
+const SCREEN_WIDTH: u32 = BOARD_WIDTH * 2 + 100;
+const SCREEN_HEIGHT: u32 = BOARD_HEIGHT + 2;
 
 enum GameUpdate {
     KeyPress(Key),
+    /// Only reported by terminals speaking the kitty keyboard protocol. Others send repeats as key presses.
+    KeyRepeat(Key),
+    /// Only reported by terminals speaking the kitty keyboard protocol.
+    KeyRelease(Key),
+    Paste(String),
     Tick,
 }
 
-//#[derive(PartialEq, Eq)]
-enum GameOver {
-    LockOut,
-    BlockOut,
-    TopOut,
+/// What happened to the result of a finished game.
+struct Results {
+    replay_path: io::Result<PathBuf>,
+    high_scores: HighScores,
+    /// A new record waiting for the player to enter their name.
+    record: Option<HighScore>,
+    name: String,
+    /// The position the game took in the high score table.
+    rank: Option<usize>,
+    error: Option<String>,
 }
 
-impl GameOver {
-    fn description(&self) -> &str {
-        match self {
-            GameOver::LockOut => panic!("The pieces are locked and cannot move."),
-            GameOver::BlockOut => panic!("The playfield is completely blocked with pieces."),
-            GameOver::TopOut => panic!("The pieces have reached the top of the playfield."),
+impl Results {
+    /// Saves the replay of a finished game and checks whether it set a new record. Records aren't offered when
+    /// the high scores couldn't be loaded, as saving the table would overwrite the file that failed to load.
+    fn new(recorder: &Recorder) -> Results {
+        let (high_scores, error) = match storage::load_high_scores() {
+            Ok(high_scores) => (high_scores, None),
+            Err(err) => (HighScores::new(), Some(format!("Could not load high scores: {}", err))),
+        };
+        let record = HighScore::from_game(recorder.game(), storage::now())
+            .filter(|entry| error.is_none() && high_scores.is_record(entry));
+
+        Results {
+            replay_path: storage::save_replay(recorder.replay()),
+            high_scores,
+            record,
+            name: String::new(),
+            rank: None,
+            error,
         }
     }
-}
-
 
+    /// Handles a key press while the player types their name for a new record. Enter adds the record to the
+    /// table and saves it.
+    fn enter_name(&mut self, key: Key) {
+        match key {
+            Key::Char(c) if !c.is_control() && self.name.chars().count() < MAX_NAME_LENGTH => self.name.push(c),
+            Key::Space if !self.name.is_empty() && self.name.chars().count() < MAX_NAME_LENGTH => self.name.push(' '),
+            Key::Backspace => {
+                self.name.pop();
+            },
+            Key::Enter => {
+                if let Some(mut record) = self.record.take() {
+                    record.name = match self.name.trim() {
+                        "" => "Player".to_string(),
+                        name => name.to_string(),
+                    };
+                    self.rank = self.high_scores.insert(record);
+                    if let Err(err) = storage::save_high_scores(&self.high_scores) {
+                        self.error = Some(format!("Could not save high scores: {}", err));
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
 
-#[derive(Debug, Copy, Clone)]
-struct Point {
-    x: i32,
-    y: i32,
+    /// Types pasted text into the name, leaving out line breaks and whatever doesn't fit.
+    fn paste(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.enter_name(if c == ' ' { Key::Space } else { Key::Char(c) });
+        }
+    }
 }
 
-struct Board {
-    cells: [[Option<Color>; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize],
-
+fn display_game_over_screen(game: &Game, results: &Results, display: &mut Display) {
+    display.clear_buffer();
+
+    // Render a game over message
+    let title = match game.config().mode {
+        Mode::Sprint { .. } if game.is_completed() => "Sprint complete!",
+        Mode::Ultra { .. } if game.is_completed() => "Time's up!",
+        _ => "Game Over!",
+    };
+    display.set_text(title, 10, 10, Color::Red, Color::Black);
+    if let Some(reason) = game.game_over_reason() {
+        display.set_text(reason.description(), 10, 11, Color::Orange, Color::Black);
+    }
+
+    // Display the player's score
+    let score_text = format!("Your Score: {}", game.score());
+    display.set_text(&score_text, 10, 12, Color::Red, Color::Black);
+
+    // Sprints are judged on time and efficiency rather than score
+    if let Mode::Sprint { .. } = game.config().mode {
+        let seconds = game.time().as_secs_f64();
+        let pps = if seconds > 0.0 { game.pieces_placed() as f64 / seconds } else { 0.0 };
+        display.set_text(&format!("Time: {}", format_time(game.time())), 10, 13, Color::Red, Color::Black);
+        display.set_text(&format!("Pieces per second: {:.2}", pps), 10, 14, Color::Red, Color::Black);
+        let faults = format!("Finesse faults: {}", game.finesse_faults());
+        display.set_text(&faults, 10, 15, Color::Red, Color::Black);
+    }
+
+    // Ultra is all about score, so show where the points came from
+    if let Mode::Ultra { .. } = game.config().mode {
+        display.set_text(&format!("Lines: {}", game.total_lines()), 10, 13, Color::Red, Color::Black);
+        display.set_text("Score by piece:", 10, 14, Color::Red, Color::Black);
+        let piece_scores: Vec<String> = PieceType::ALL.iter()
+            .map(|kind| format!("{:?} {}", kind, game.piece_score(*kind)))
+            .collect();
+        display.set_text(&piece_scores.join("  "), 10, 15, Color::Blue, Color::Black);
+    }
+
+    display_high_scores(&results.high_scores, game.config().mode, results.rank, 56, 2, display);
+
+    // Ask for a name when the game set a new record, otherwise prompt the player to restart or exit
+    if results.record.is_some() {
+        let prompt = format!("New record! Enter your name: {}_", results.name);
+        display.set_text(&prompt, 10, 17, Color::Orange, Color::Black);
+        display.set_text("Press Enter to save it.", 10, 18, Color::Orange, Color::Black);
+    } else {
+        display.set_text("Press 'R' to restart or 'Q' for the menu.", 10, 17, Color::Red, Color::Black);
+    }
+
+    let saved = match &results.replay_path {
+        Ok(path) => format!("Replay saved to {}", path.display()),
+        Err(err) => format!("Could not save replay: {}", err),
+    };
+    display.set_text(&saved, 10, 19, Color::Red, Color::Black);
+    if let Some(err) = &results.error {
+        display.set_text(err, 10, 20, Color::Red, Color::Black);
+    }
 }
 
-impl Board {
-    pub fn render(&self, display: &mut Display) {
-        for y in HIDDEN_ROWS..BOARD_HEIGHT {
-            display.set_text("|", 0, y, Color::Red, Color::Black);
-            display.set_text("|", BOARD_WIDTH * 2 + 1, y, Color::Red, Color::Black);
-        }
-        for x in 0..(BOARD_WIDTH * 2 + 1) {
-            display.set_text("-", x, BOARD_HEIGHT, Color::Red, Color::Black);
-        }
-        for row in 0..BOARD_HEIGHT {
-            for col in 0..BOARD_WIDTH {
-                if let Some(color) = self.cells[row as usize][col as usize] {
-                    let c = 1 + (col * 2);
-                    display.set_text(" ", c, row, color, color);
-                    display.set_text(" ", c + 1, row, color, color);
-                }
-            }
-        }
-
-       
-    
+/// Draws the high score table of a mode with its top left corner at the given position, highlighting the
+/// entry at `highlight`.
+fn display_high_scores(high_scores: &HighScores, mode: Mode, highlight: Option<usize>, x: u32, y: u32,
+                       display: &mut Display) {
+    display.set_text(&format!("High Scores: {}", mode), x, y, Color::Red, Color::Black);
+    let header = format!("    {:<12} {:>7} {:>5} {:>3} {:>9} {:<10}", "Name", "Score", "Lines", "Lvl", "Time", "Date");
+    display.set_text(&header, x, y + 1, Color::Red, Color::Black);
+
+    let table = high_scores.table(mode);
+    if table.is_empty() {
+        display.set_text("No results yet.", x + 4, y + 2, Color::Blue, Color::Black);
+    }
+    for (i, entry) in table.iter().take(MAX_ENTRIES).enumerate() {
+        let row = format!(
+            "{:>2}. {:<12} {:>7} {:>5} {:>3} {:>9} {}",
+            i + 1,
+            entry.name,
+            entry.score,
+            entry.lines,
+            entry.level,
+            format_time(entry.time),
+            entry.date_string(),
+        );
+        let color = if highlight == Some(i) { Color::Orange } else { Color::Blue };
+        display.set_text(&row, x, y + 2 + i as u32, color, Color::Black);
     }
+}
 
-    pub fn lock_piece(&mut self, piece: &Piece, origin: Point) {
-        piece.each_point(&mut |row, col| {
-            let x = origin.x + (col as i32);
-            let y = origin.y + (row as i32);
-            self.cells[y as usize][x as usize] = Some(piece.color);
+/// Spawns the threads feeding the main loop: one sends a game tick every frame, the other listens for keyboard
+/// input.
+fn spawn_event_threads() -> mpsc::Receiver<GameUpdate> {
+    let (tx_event, rx_event) = mpsc::channel();
+
+    // Spawn a thread which sends a game tick every frame. The engine works out gravity and lock delays from
+    // the time that passes between ticks.
+    {
+        let tx_event = tx_event.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(FRAME);
+                tx_event.send(GameUpdate::Tick).unwrap();
+            };
         });
     }
 
-    pub fn collision_test(&self, piece: &Piece, origin: Point) -> bool {
-        let mut found = false;
-        piece.each_point(&mut |row, col| {
-            if !found {
-                let x = origin.x + col;
-                let y = origin.y + row;
-                if x < 0 || x >= (BOARD_WIDTH as i32) || y < 0 || y >= (BOARD_HEIGHT as i32) ||
-                    self.cells[y as usize][x as usize] != None {
-                  found = true;
+    // Spawn a thread which reads keyboard input as it arrives, and one which decodes it. A lone Escape only
+    // differs from the start of an escape sequence in that nothing follows it for a while.
+    let (tx_bytes, rx_bytes) = mpsc::channel();
+    thread::spawn(move || {
+        use std::io::Read;
+
+        let mut buffer = [0u8; 256];
+        loop {
+            match std::io::stdin().read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => tx_bytes.send(buffer[..n].to_vec()).unwrap(),
+                Err(msg) => panic!("could not read from standard in: {}", msg),
+            }
+        }
+    });
+    {
+        let tx_event = tx_event.clone();
+        thread::spawn(move || {
+            let mut decoder = Decoder::new();
+            loop {
+                let events = if decoder.is_pending() {
+                    match rx_bytes.recv_timeout(ESC_TIMEOUT) {
+                        Ok(bytes) => decoder.feed(&bytes),
+                        Err(mpsc::RecvTimeoutError::Timeout) => decoder.timeout(),
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match rx_bytes.recv() {
+                        Ok(bytes) => decoder.feed(&bytes),
+                        Err(_) => break,
+                    }
+                };
+                for update in events.into_iter().filter_map(game_update) {
+                    tx_event.send(update).unwrap();
                 }
             }
         });
-
-        found
     }
 
-    /// Clears the board of any complete lines, shifting down rows to take their place.
-    /// Returns the total number of lines that were cleared.
-    fn clear_lines(&mut self) -> u32 {
-        let mut cleared_lines: usize = 0;
-        for row in (0..self.cells.len()).rev() {
-            if (row as i32) - (cleared_lines as i32) < 0 {
-                break;
-            }
-
-            if cleared_lines > 0 {
-                self.cells[row] = self.cells[row - cleared_lines];
-                self.cells[row - cleared_lines] = [None; BOARD_WIDTH as usize];
-            }
+    rx_event
+}
 
-            while !self.cells[row].iter().any(|x| *x == None) {
-                cleared_lines += 1;
-                self.cells[row] = self.cells[row - cleared_lines];
-                self.cells[row - cleared_lines] = [None; BOARD_WIDTH as usize];
-            }
-        }
+/// Maps a decoded input event to the update the screens act on. Keys pressed with Ctrl or Alt aren't bound to
+/// anything, apart from Ctrl-C.
+fn game_update(event: Event) -> Option<GameUpdate> {
+    match event {
+        Event::Press(key, modifiers) if key == Key::CtrlC || !(modifiers.ctrl || modifiers.alt) => {
+            Some(GameUpdate::KeyPress(key))
+        },
+        Event::Repeat(key, modifiers) if !(modifiers.ctrl || modifiers.alt) => Some(GameUpdate::KeyRepeat(key)),
+        Event::Release(key, modifiers) if !(modifiers.ctrl || modifiers.alt) => Some(GameUpdate::KeyRelease(key)),
+        Event::Press(..) | Event::Repeat(..) | Event::Release(..) => None,
+        Event::Paste(text) => Some(GameUpdate::Paste(text)),
+        Event::KeyboardFlags(flags) => {
+            // The second flag asks for key release events
+            RELEASES_REPORTED.store(flags & 2 != 0, Ordering::Relaxed);
+            None
+        },
+    }
+}
 
-        cleared_lines as u32
+/// Saves the replay of a game left before it ended. Finished games are saved along with their results, and games
+/// left before their first frame aren't worth keeping.
+fn save_unfinished(recorder: &Recorder) {
+    if !recorder.game().is_game_over() && recorder.replay().frames > 0 {
+        // The player is already leaving the game, so there's nowhere left to report a failure
+        let _ = storage::save_replay(recorder.replay());
     }
 }
 
-struct Piece {
-    color: Color,
-    shape: Vec<Vec<u8>>,
+/// Replaces the game with a new one using the same settings and a new seed, saving the replay of the old one.
+fn restart(recorder: &mut Recorder) {
+    save_unfinished(recorder);
+    let config = Config {
+        seed: rand::random(),
+        ..*recorder.game().config()
+    };
+    *recorder = Recorder::new(config);
 }
 
-impl Clone for Piece {
-    fn clone(&self) -> Piece {
-        let mut p = Piece{
-            color: self.color,
-            shape: Vec::with_capacity(self.shape.len())
-        };
-        for row in &self.shape {
-            p.shape.push(row.clone());
-        }
-        p
-    }
+/// The states a game session moves through.
+enum Session {
+    Playing,
+    Paused(Menu),
+    GameOver(Results),
 }
 
-impl Piece {
-    pub fn new_o() -> Piece {
-        Piece{
-            color: Color::Cyan,
-            shape: vec![vec![1, 1],
-                        vec![1, 1]]
+/// Runs a game session until the player leaves it, returning the screen to show next. Game over leads to the
+/// results screen, from which the player can restart or go back to the title screen. Ctrl-C quits from anywhere.
+/// Every game played is saved as a replay, including those restarted or left before they ended.
+fn play(recorder: &mut Recorder, display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>,
+        bindings: &Bindings) -> Screen {
+    let mut session = Session::Playing;
+    let mut held = HeldInputs::new();
+
+    // Main game loop. The loop listens and responds to timer and keyboard updates received on a channel
+    // as sent by the event threads.
+    let screen = loop {
+        if let Session::Playing = session {
+            if recorder.game().is_game_over() {
+                session = Session::GameOver(Results::new(recorder));
+            }
         }
-    }
 
-    pub fn new_l() -> Piece {
-        Piece{
-            color: Color::Orange,
-            shape: vec![vec![0, 0, 1],
-                        vec![1, 1, 1],
-                        vec![0, 0, 0]]
+        display.clear_buffer();
+        match &session {
+            Session::Playing => render::render(recorder.game(), &mut AnsiRenderer::new(display)),
+            Session::Paused(menu) => {
+                render::render_paused(recorder.game(), menu.items(), menu.selected(), &mut AnsiRenderer::new(display))
+            },
+            Session::GameOver(results) => display_game_over_screen(recorder.game(), results, display),
         }
-    }
+        display.render();
 
-    pub fn new_j() -> Piece {
-        Piece{
-            color: Color::Blue,
-            shape: vec![vec![1, 0, 0],
-                        vec![1, 1, 1],
-                        vec![0, 0, 0]]
+        let key = match rx_event.recv() {
+            Ok(GameUpdate::KeyPress(key)) => key,
+            Ok(GameUpdate::KeyRepeat(key)) => {
+                // Only moves and soft drop repeat while held, which the game times itself
+                let input = bindings.action(&key).and_then(|action| action.input());
+                if let (Session::Playing, Some(input @ (Input::MoveLeft | Input::MoveRight | Input::SoftDrop))) =
+                    (&session, input)
+                {
+                    held.press(input, recorder);
+                }
+                continue;
+            },
+            Ok(GameUpdate::KeyRelease(key)) => {
+                let input = bindings.action(&key).and_then(|action| action.input());
+                if let (Session::Playing, Some(input)) = (&session, input) {
+                    held.release(input, recorder);
+                }
+                continue;
+            },
+            Ok(GameUpdate::Paste(text)) => {
+                if let Session::GameOver(results) = &mut session {
+                    if results.record.is_some() {
+                        results.paste(&text);
+                    }
+                }
+                continue;
+            },
+            Ok(GameUpdate::Tick) => {
+                // Stepping by a fixed frame keeps the game deterministic regardless of scheduling. The game clock
+                // only moves through these steps, so skipping them freezes gravity, lock delay and the mode's
+                // clock while paused.
+                if let Session::Playing = session {
+                    held.expire(recorder);
+                    recorder.step();
+                }
+                continue;
+            },
+            Err(err) => panic!("{}", err),
+        };
+        if key == Key::CtrlC {
+            break Screen::Quit;
         }
-    }
 
-    pub fn new_t() -> Piece {
-        Piece{
-            color: Color::Purple,
-            shape: vec![vec![0, 1, 0],
-                        vec![1, 1, 1],
-                        vec![0, 0, 0]]
+        match &mut session {
+            Session::Playing => {
+                match bindings.action(&key) {
+                    Some(Action::Pause) => {
+                        // Keys let go while paused aren't seen, so don't leave anything held
+                        held.release_all(recorder);
+                        session = Session::Paused(Menu::new(&["Resume", "Restart", "Quit"]));
+                    },
+                    Some(Action::Restart) => {
+                        restart(recorder);
+                        held = HeldInputs::new();
+                    },
+                    Some(Action::Quit) => break Screen::Title,
+                    Some(action) => {
+                        if let Some(input) = action.input() {
+                            held.press(input, recorder);
+                        }
+                    },
+                    None => (),
+                }
+            },
+            Session::Paused(menu) => {
+                match menu.handle(&key) {
+                    Some(0) => session = Session::Playing,
+                    Some(1) => {
+                        restart(recorder);
+                        held = HeldInputs::new();
+                        session = Session::Playing;
+                    },
+                    Some(_) => break Screen::Title,
+                    None if bindings.action(&key) == Some(Action::Pause) => session = Session::Playing,
+                    None => (),
+                }
+            },
+            Session::GameOver(results) if results.record.is_some() => results.enter_name(key),
+            Session::GameOver(_) => {
+                match (key, bindings.action(&key)) {
+                    (Key::Char('r'), _) | (_, Some(Action::Restart)) => {
+                        restart(recorder);
+                        held = HeldInputs::new();
+                        session = Session::Playing;
+                    },
+                    (Key::Char('q'), _) | (Key::Escape, _) | (_, Some(Action::Quit)) => break Screen::Title,
+                    _ => (),
+                }
+            },
         }
-    }
+    };
 
-    pub fn new_s() -> Piece {
-        Piece{
-            color: Color::Green,
-            shape: vec![vec![0, 1, 1],
-                        vec![1, 1, 0],
-                        vec![0, 0, 0]]
-        }
-    }
+    save_unfinished(recorder);
+    screen
+}
 
-    pub fn new_z() -> Piece {
-        Piece{
-            color: Color::Red,
-            shape: vec![vec![1, 1, 0],
-                        vec![0, 1, 1],
-                        vec![0, 0, 0]]
-        }
-    }
+/// Playback speeds the replay viewer cycles through, as multiples of real time.
+const REPLAY_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
 
-    pub fn new_i() -> Piece {
-        Piece{
-            color: Color::Cyan,
-            shape: vec![vec![0, 0, 0, 0],
-                        vec![1, 1, 1, 1],
-                        vec![0, 0, 0, 0],
-                        vec![0, 0, 0, 0]]
-        }
-    }
+/// Plays a recorded game back, with keys to pause, step frame by frame and change the speed.
+fn watch_replay(playback: &mut Playback, display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>) -> Screen {
+    let mut paused = false;
+    let mut speed = 2;
+    let mut frames_due = 0.0;
 
-    fn rotate(&mut self, direction: Direction) {
-        let size = self.shape.len();
+    loop {
+        display.clear_buffer();
+        render::render(playback.game(), &mut AnsiRenderer::new(display));
 
-        for row in 0..size/2 {
-            for col in row..(size - row - 1) {
-                let t = self.shape[row][col];
+        let state = if playback.is_finished() {
+            "finished"
+        } else if paused {
+            "paused"
+        } else {
+            ""
+        };
+        let status = format!("Replay  frame {}/{}  {}x  {}", playback.frame(), playback.replay().frames,
+                             REPLAY_SPEEDS[speed], state);
+        display.set_text(&status, 0, BOARD_HEIGHT + 1, Color::Red, Color::Black);
+        let help = "Space: pause  N: step  +/-: speed  Q: quit";
+        display.set_text(help, 50, BOARD_HEIGHT + 1, Color::Blue, Color::Black);
+        display.render();
 
-                match direction {
-                    Direction::Left => {
-                        self.shape[row][col] = self.shape[col][size - row - 1];
-                        self.shape[col][size - row - 1] = self.shape[size - row - 1][size - col - 1];
-                        self.shape[size - row - 1][size - col - 1] = self.shape[size - col - 1][row];
-                        self.shape[size - col - 1][row] = t;
-                    },
-                    Direction::Right => {
-                        self.shape[row][col] = self.shape[size - col - 1][row];
-                        self.shape[size - col - 1][row] = self.shape[size - row - 1][size - col - 1];
-                        self.shape[size - row - 1][size - col - 1] = self.shape[col][size - row - 1];
-                        self.shape[col][size - row - 1] = t;
-                    }
+        match rx_event.recv() {
+            Ok(GameUpdate::KeyPress(key)) => {
+                match key {
+                    Key::Char('q') | Key::Escape => return Screen::Replays,
+                    Key::CtrlC => return Screen::Quit,
+                    Key::Space | Key::Char('p') => paused = !paused,
+                    Key::Char('n') | Key::Char('.') if paused => playback.step(),
+                    Key::Char('+') | Key::Char('=') => speed = (speed + 1).min(REPLAY_SPEEDS.len() - 1),
+                    Key::Char('-') => speed = speed.saturating_sub(1),
+                    _ => (),
                 }
             }
-        }
-    }
-
-    fn each_point(&self, callback: &mut dyn FnMut(i32, i32)) {
-        let piece_width = self.shape.len() as i32;
-        for row in 0..piece_width {
-            for col in 0..piece_width {
-                if self.shape[row as usize][col as usize] != 0 {
-                    callback(row, col);
+            Ok(GameUpdate::KeyRepeat(_)) | Ok(GameUpdate::KeyRelease(_)) | Ok(GameUpdate::Paste(_)) => (),
+            Ok(GameUpdate::Tick) => {
+                if !paused {
+                    frames_due += REPLAY_SPEEDS[speed];
+                    while frames_due >= 1.0 && !playback.is_finished() {
+                        playback.step();
+                        frames_due -= 1.0;
+                    }
                 }
             }
+            Err(err) => panic!("{}", err),
         }
     }
 }
 
-/// Implements a queue of randomized tetrominoes.
-///
-/// Instead of a purely random stream of tetromino types, this queue generates a random ordering of all
-/// possible types and ensures all of those pieces are used before re-generating a new random set. This helps
-/// avoid pathological cases where purely random generation provides the same piece type repeately in a row,
-/// or fails to provide a required piece for a very long time.
-struct PieceBag {
-    pieces: [Option<Piece>;7] 
+/// The modes offered on the mode select screen and in the high score tables.
+const MODES: [Mode; 5] = [
+    Mode::Marathon,
+    Mode::Sprint { lines: SPRINT_LINES[0] },
+    Mode::Sprint { lines: SPRINT_LINES[1] },
+    Mode::Sprint { lines: SPRINT_LINES[2] },
+    Mode::Ultra { time_limit: ULTRA_TIME },
+];
+
+/// The most replays listed on the replays screen.
+const MAX_LISTED_REPLAYS: usize = 14;
+
+/// The screens of the frontend. Each screen runs until the player leaves it and returns the one to show next.
+enum Screen {
+    Title,
+    ModeSelect,
+    Settings,
+    Controls,
+    HighScores,
+    Replays,
+    Play(Mode),
+    Watch(Replay),
+    Quit,
 }
 
-impl PieceBag {
-    fn new() -> PieceBag {
-        let mut p = PieceBag{
-            pieces: [None, None, None, None, None, None, None]
-        };
-        p.fill_bag();
-        p
-    }
-
-    /// Removes and returns the next piece in the queue.
-    fn pop(&mut self) -> Piece {
-        if let Some(piece) = self.pieces[0].take() {
-            // Shift the remaining pieces to the front
-            for i in 0..(6) {
-                self.pieces[i] = self.pieces[i + 1].take();
-            }
-            // Fill the last slot with a new piece
-            if self.pieces[6].is_none() {
-                self.fill_bag();
-            }
-            piece.clone()
-        } else {
-            self.fill_bag();
-            self.pop()
-        }
-    }
-
-    /// Returns a copy of the next piece in the queue.
-    fn peek(&self) -> Piece {
-        match &self.pieces[0] {
-            Some(p) => p.clone(),
-            None => panic!("No next piece in piece bag")
+/// Waits for the next key press, ignoring game ticks. Held keys repeat, to scroll through menus and values.
+fn next_key(rx_event: &mpsc::Receiver<GameUpdate>) -> Key {
+    loop {
+        match rx_event.recv() {
+            Ok(GameUpdate::KeyPress(key)) | Ok(GameUpdate::KeyRepeat(key)) => return key,
+            Ok(GameUpdate::KeyRelease(_)) | Ok(GameUpdate::Paste(_)) | Ok(GameUpdate::Tick) => (),
+            Err(err) => panic!("{}", err),
         }
     }
-
-    /// Generates a random ordering of all possible pieces and adds them to the piece queue.
-    fn fill_bag(&mut self) {
-        //use rand::Rng;
-
-        let mut pieces: [Option<Piece>;7] = [
-            Some(Piece::new_o()),
-            Some(Piece::new_l()),
-            Some(Piece::new_j()),
-            Some(Piece::new_t()),
-            Some(Piece::new_s()),
-            Some(Piece::new_z()),
-            Some(Piece::new_i())
-        ];
-
-        let mut rng = rand::thread_rng();
-        let mut indices: Vec<usize> = (0..7).collect();
-        indices.shuffle(&mut rng);
-    
-        for i in 0..7 {
-            if let Some(piece) = pieces[indices[i]].take() {
-                self.pieces[i] = Some(piece.clone());
-            }
-        }
-}
 }
 
-struct Game {
-    board: Board,
-    piece_bag: PieceBag,
-    piece: Piece,
-    piece_position: Point,
-    score: u32,
-    level: u32,       
-    total_lines: u32, 
-    game_over: bool,
+/// Returns true for the keys that leave a menu screen.
+fn is_back_key(key: &Key) -> bool {
+    matches!(key, Key::Char('q') | Key::Backspace | Key::Escape)
 }
 
-impl Game {
-    fn new() -> Game {
-        let mut piece_bag = PieceBag::new();
-        let piece = piece_bag.pop();
-
-        let mut game = Game {
-            board: Board{
-                cells: [[None; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize]
-            },
-            piece_bag: piece_bag,
-            piece: piece,
-            piece_position: Point{ x: 0, y: 0 },
-            level: 0,           
-            score: 0,          
-            total_lines: 0,    
-            game_over: false,
-        };
+/// Draws a screen's title and the key help along the bottom.
+fn render_frame(display: &mut Display, title: &str, help: &str) {
+    display.set_text(title, 10, 2, Color::Red, Color::Black);
+    display.set_text(help, 10, SCREEN_HEIGHT - 1, Color::Blue, Color::Black);
+}
 
+fn title_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>) -> Screen {
+    let mut menu = Menu::new(&["Play", "Settings", "High Scores", "Replays", "Quit"]);
+    let x = (SCREEN_WIDTH - LOGO_WIDTH) / 2;
 
+    loop {
+        display.clear_buffer();
+        menu::render_logo(display, x, 3);
+        menu.render(display, x + LOGO_WIDTH / 2 - 6, 11);
+        display.set_text("Arrows: move  Enter: select", x + LOGO_WIDTH / 2 - 13, SCREEN_HEIGHT - 1, Color::Blue,
+                         Color::Black);
+        display.render();
 
-        game.place_new_piece();
-        game
+        let key = next_key(rx_event);
+        if key == Key::CtrlC {
+            return Screen::Quit;
+        }
+        match menu.handle(&key) {
+            Some(0) => return Screen::ModeSelect,
+            Some(1) => return Screen::Settings,
+            Some(2) => return Screen::HighScores,
+            Some(3) => return Screen::Replays,
+            Some(_) => return Screen::Quit,
+            None => (),
+        }
     }
+}
 
+fn mode_select_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>) -> Screen {
+    let mut items: Vec<String> = MODES.iter().map(|mode| mode.to_string()).collect();
+    items.push("Back".to_string());
+    let mut menu = Menu::new(&[]);
+    menu.set_items(items);
 
+    loop {
+        display.clear_buffer();
+        render_frame(display, "Select a mode", "Arrows: move  Enter: play  Q: back");
+        menu.render(display, 10, 4);
+        display.render();
 
-    /// Returns the new position of the current piece if it were to be dropped.
-    fn find_dropped_position(&self) -> Point {
-        let mut origin = self.piece_position;
-        while !self.board.collision_test(&self.piece, origin) {
-            origin.y += 1;
+        let key = next_key(rx_event);
+        if key == Key::CtrlC {
+            return Screen::Quit;
         }
-        origin.y -= 1;
-        origin
-    }
-
-    fn game_over_condition(&self) -> bool {
-        // If the game is already marked as game over, no need to check again
-        if self.game_over {
-            return true;
+        if is_back_key(&key) {
+            return Screen::Title;
         }
-    
-        // Check if a new piece cannot be placed
-        let new_piece = self.piece_bag.peek();
-        let origin = Point {
-            x: ((BOARD_WIDTH - (new_piece.shape.len() as u32)) / 2) as i32,
-            y: 0,
-        };
-        if self.board.collision_test(&new_piece, origin) {
-            return true;
+        match menu.handle(&key) {
+            Some(i) if i < MODES.len() => return Screen::Play(MODES[i]),
+            Some(_) => return Screen::Title,
+            None => (),
         }
-    
-        false
     }
+}
 
+/// Returns the value after (or before) the current one in `values`, wrapping around at either end.
+fn cycle<T: PartialEq + Copy>(values: &[T], current: T, forward: bool) -> T {
+    let i = values.iter().position(|value| *value == current).unwrap_or(0);
+    let len = values.len();
+    values[if forward { (i + 1) % len } else { (i + len - 1) % len }]
+}
 
-    /// Draws the game to the display.
-    fn render(&self, display: &mut Display) {
-        // Render the board
-        self.board.render(display);
-
-        // Render the level
-        let left_margin = BOARD_WIDTH * 2 + 5;
-        display.set_text("Level: 1", left_margin, 3, Color::Red, Color::Black);
-        let score_line = format!("Score: {}",self.score);
-        display.set_text(&score_line, left_margin, 4, Color::Red, Color::Black);
-        
-        // Define left_margin before using it
-        //let left_margin = BOARD_WIDTH * 2 + 5;
-
- 
-
-       /* // Create strings as owned `String` instances
-        let level_text = format!("Level: {}", self.level);
-        let score_text = format!("Score: {}", self.score);
-        let lines_cleared_text = format!("Lines Cleared: {}", self.total_lines);
-
-        // Render the level
-        display.set_text(&level_text, left_margin, 3, Color::Red, Color::Black);
-
-        // Render the score
-        display.set_text(&score_text, left_margin, 4, Color::Red, Color::Black);
-
-        // Render the lines cleared
-        display.set_text(&lines_cleared_text, left_margin, 5, Color::Red, Color::Black);
-                        
-*/
-
-        // Render the currently falling piece
-        let x = 1 + (2 * self.piece_position.x);
-        self.render_piece(display, &self.piece, Point{ x: x, y: self.piece_position.y });
-
-        // Render a ghost piece
-        let ghost_position = self.find_dropped_position();
-        self.render_piece(display, &self.piece, Point{ x: x, y: ghost_position.y });
+/// The DAS, ARR and soft drop factors the settings screen cycles through.
+const DAS_VALUES: [Duration; 10] = [
+    Duration::from_millis(50),
+    Duration::from_millis(67),
+    Duration::from_millis(83),
+    Duration::from_millis(100),
+    Duration::from_millis(117),
+    Duration::from_millis(133),
+    Duration::from_millis(150),
+    Duration::from_millis(167),
+    Duration::from_millis(200),
+    Duration::from_millis(250),
+];
+const ARR_VALUES: [Duration; 6] = [
+    Duration::ZERO,
+    Duration::from_millis(17),
+    Duration::from_millis(33),
+    Duration::from_millis(50),
+    Duration::from_millis(67),
+    Duration::from_millis(83),
+];
+const SOFT_DROP_FACTORS: [u32; 6] = [5, 10, 20, 40, 80, 1000];
+
+/// Lets the player pick the scoring model, randomizer, next queue length, handling and 180° kicks used by new
+/// games.
+fn settings_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>, config: &mut Config) -> Screen {
+    let next_counts: Vec<usize> = (1..=MAX_PREVIEW).collect();
+    let mut menu = Menu::new(&[]);
+
+    loop {
+        menu.set_items(vec![
+            format!("Scoring: {:?}", config.scoring),
+            format!("Randomizer: {:?}", config.randomizer),
+            format!("Next pieces: {}", config.next_count),
+            format!("DAS: {} ms", config.das.as_millis()),
+            format!("ARR: {} ms", config.arr.as_millis()),
+            format!("Soft drop factor: {}x", config.soft_drop_factor),
+            format!("180 kicks: {:?}", config.flip_kicks),
+            "Controls".to_string(),
+            "Back".to_string(),
+        ]);
 
-        // Render the next piece
-        display.set_text("Next piece:", left_margin, 7, Color::Red, Color::Black);
-        let next_piece = self.piece_bag.peek();
-        self.render_piece(display, &next_piece, Point{ x: (left_margin as i32) + 2, y: 9 });
-    }
-
-    fn display_game_over_screen(&self, display: &mut Display) {
         display.clear_buffer();
-
-        // Render a game over message
-        display.set_text("Game Over!", 10, 10, Color::Red, Color::Black);
-
-        // Display the player's score
-        let score_text = format!("Your Score: {}", self.score);
-        display.set_text(&score_text, 10, 12, Color::Red, Color::Black);
-
-        // Prompt the player to restart or exit
-        display.set_text("Press 'R' to restart or 'Q' to quit.", 10, 14, Color::Red, Color::Black);
-
+        render_frame(display, "Settings", "Arrows: move  Left/Right: change  Q: back");
+        menu.render(display, 10, 4);
         display.render();
-    }
-
 
-    fn render_piece(&self, display: &mut Display, piece: &Piece, origin: Point) {
-        let color = piece.color;
-
-        piece.each_point(&mut |row, col| {
-            let x = (origin.x + 2 * col) as u32;
-            let y = (origin.y + row) as u32;
-            display.set_text(" ", x, y, color, color);
-            display.set_text(" ", x + 1, y, color, color);
-        });
-    }
-
-    /// Moves the current piece in the specified direction. Returns true if the piece could be moved and
-    /// didn't collide.
-    fn move_piece(&mut self, x: i32, y: i32) -> bool {
-        let new_position = Point{
-            x: self.piece_position.x + x,
-            y: self.piece_position.y + y,
-        };
-        if self.board.collision_test(&self.piece, new_position) {
-            false
-        } else {
-            self.piece_position = new_position;
-            true
+        let key = next_key(rx_event);
+        if key == Key::CtrlC {
+            return Screen::Quit;
         }
-    }
-
-    /// Rotates the current piece in the specified direction. Returns true if the piece could be rotated
-    /// without any collisions.
-    fn rotate_piece(&mut self, direction: Direction) -> bool {
-        let mut new_piece = self.piece.clone();
-        new_piece.rotate(direction);
-
-        if self.board.collision_test(&new_piece, self.piece_position) {
-            false
-            //GAME OVER
-        } else {
-            self.piece = new_piece;
-            true
+        if is_back_key(&key) {
+            return Screen::Title;
         }
-    }
-
-    /// Positions the current piece at the top of the board. Returns true if the piece can be placed without
-    /// any collisions.
-    fn place_new_piece(&mut self) -> bool {
-        let origin = Point{
-            x: ((BOARD_WIDTH - (self.piece.shape.len() as u32)) / 2) as i32,
-            y: 0,
+        let forward = match key {
+            Key::Left | Key::Char('a') => false,
+            Key::Right | Key::Char('d') => true,
+            _ => match menu.handle(&key) {
+                Some(7) => return Screen::Controls,
+                Some(8) => return Screen::Title,
+                Some(_) => true,
+                None => continue,
+            },
         };
-        if self.board.collision_test(&self.piece, origin) {
-            false
-        } else {
-            self.piece_position = origin;
-            true
+        match menu.selected() {
+            0 => config.scoring = cycle(&Scoring::ALL, config.scoring, forward),
+            1 => config.randomizer = cycle(&RandomizerKind::ALL, config.randomizer, forward),
+            2 => config.next_count = cycle(&next_counts, config.next_count, forward),
+            3 => config.das = cycle(&DAS_VALUES, config.das, forward),
+            4 => config.arr = cycle(&ARR_VALUES, config.arr, forward),
+            5 => config.soft_drop_factor = cycle(&SOFT_DROP_FACTORS, config.soft_drop_factor, forward),
+            6 => config.flip_kicks = cycle(&FlipKicks::ALL, config.flip_kicks, forward),
+            _ => (),
         }
     }
+}
 
-    /// Advances the game by moving the current piece down one step. If the piece cannot move down, the piece
-    /// is locked and the game is set up to drop the next piece.  Returns true if the game could be advanced,
-    /// false if the player has lost.
-    fn advance_game(&mut self) -> bool {
-        if !self.move_piece(0, 1) {
-            self.board.lock_piece(&self.piece, self.piece_position);
-
-            let lines_cleared = self.board.clear_lines();
-            if lines_cleared > 0 {
-                // Update the score based on the number of lines cleared
-                self.score += match lines_cleared {
-                    1 => 40,   // Scoring for clearing one line
-                    2 => 100,  // Scoring for clearing two lines
-                    3 => 300,  // Scoring for clearing three lines
-                    4 => 1200, // Scoring for clearing four lines 
-                    _ => 0,    // Default scoring for other cases
-                };
-
-            self.total_lines += lines_cleared;
+/// Saves the key bindings, returning the message telling the player how that went.
+fn save_bindings(bindings: &Bindings) -> (String, Color) {
+    match storage::save_bindings(bindings) {
+        Ok(()) => (format!("Saved to {}", storage::bindings_path().display()), Color::Blue),
+        Err(err) => (format!("Could not save key bindings: {}", err), Color::Red),
+    }
+}
 
-            if lines_cleared > 0 && self.total_lines >= self.level * 10 {
-                // Level up every 10 lines cleared
-                self.level += 1;
-            }
+/// Lists the keys bound to each action, and lets the player bind more keys or clear them. Changes are saved to
+/// the key bindings file straight away.
+fn controls_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>, bindings: &mut Bindings) -> Screen {
+    let mut menu = Menu::new(&[]);
+    // The action waiting for a key, and the key bound to another action if it was pressed once already
+    let mut binding: Option<(Action, Option<Key>)> = None;
+    let mut message: Option<(String, Color)> = None;
+
+    loop {
+        let mut items: Vec<String> = Action::ALL.iter().map(|action| {
+            let keys: Vec<String> = bindings.keys(*action).iter().map(|key| key.to_string()).collect();
+            format!("{:<14} {}", action.label(), if keys.is_empty() { "-".to_string() } else { keys.join(", ") })
+        }).collect();
+        items.push("Reset to defaults".to_string());
+        items.push("Back".to_string());
+        menu.set_items(items);
 
+        display.clear_buffer();
+        let help = match binding {
+            Some(_) => "Press the key to bind",
+            None => "Arrows: move  Enter: add a key  Backspace: clear  Q: back",
+        };
+        render_frame(display, "Controls", help);
+        menu.render(display, 10, 4);
+        if let Some((text, color)) = &message {
+            display.set_text(text, 10, 18, *color, Color::Black);
         }
+        display.render();
 
+        let key = next_key(rx_event);
+        if key == Key::CtrlC {
+            return Screen::Quit;
+        }
 
-            self.piece = self.piece_bag.pop();
-
-            if !self.place_new_piece() {
-
-                if self.piece_position.y <= HIDDEN_ROWS as i32 {
-                    //GameOver::TopOut.description();
-                    self.game_over = true;
-                    return false;
-                } else if self.board.collision_test(&self.piece, self.piece_position) {
-                    //GameOver::LockOut.description();
-                    self.game_over = true;
-                    return false;
-                } else {
-                    //GameOver::BlockOut.description();
-                    self.game_over = true;
-                    return false;
-                }
-
-                
+        if let Some((action, pressed)) = binding {
+            // Any key can be bound, but one bound to another action has to be pressed twice to move it over
+            if pressed == Some(key) {
+                bindings.unbind(&key);
             }
-
+            binding = None;
+            message = match bindings.bind(action, key) {
+                Ok(()) => Some(save_bindings(bindings)),
+                Err(err) if bindings.action(&key).is_some() => {
+                    binding = Some((action, Some(key)));
+                    Some((format!("{}. Press it again to move it to {}.", err, action.label()), Color::Orange))
+                },
+                Err(err) => Some((err, Color::Red)),
+            };
+            continue;
         }
 
-        true
-    }
-
-    /// Drops the current piece to the lowest spot on the board where it fits without collisions and
-    /// advances the game.
-    fn drop_piece(&mut self) -> bool {
-        while self.move_piece(0, 1) {}
-        self.advance_game()
-    }
-
-    fn keypress(&mut self, key: Key) {
         match key {
-            Key::Left => self.move_piece(-1, 0),
-            Key::Right => self.move_piece(1, 0),
-            Key::Down => self.advance_game(),
-            Key::Up => self.rotate_piece(Direction::Left),
-            Key::Space => self.drop_piece(),
-            Key::Char('q') => self.rotate_piece(Direction::Left),
-            Key::Char('e') => self.rotate_piece(Direction::Right),
-            _ => false,
-        };
+            Key::Char('q') | Key::Escape => return Screen::Settings,
+            Key::Backspace | Key::Delete => {
+                if let Some(action) = Action::ALL.get(menu.selected()) {
+                    bindings.clear(*action);
+                    message = Some(save_bindings(bindings));
+                }
+            },
+            _ => match menu.handle(&key) {
+                Some(i) if i < Action::ALL.len() => {
+                    binding = Some((Action::ALL[i], None));
+                    message = Some((format!("Press a key for {}", Action::ALL[i].label()), Color::Orange));
+                },
+                Some(i) if i == Action::ALL.len() => {
+                    *bindings = Bindings::default();
+                    message = Some(save_bindings(bindings));
+                },
+                Some(_) => return Screen::Settings,
+                None => (),
+            },
+        }
     }
+}
 
+/// Shows the high score table of every mode, switching between them with Left and Right.
+fn high_scores_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>) -> Screen {
+    let (high_scores, error) = match storage::load_high_scores() {
+        Ok(high_scores) => (high_scores, None),
+        Err(err) => (HighScores::new(), Some(format!("Could not load high scores: {}", err))),
+    };
+    let mut mode = 0;
 
-    fn play(&mut self, display: &mut Display) {
-        let (tx_event, rx_event) = mpsc::channel();
-
-        // Spawn a thread which sends periodic game ticks to advance the piece
-        {
-            // Formula: speed (in milliseconds) = 1000 - (level * 50)
-            let level_speed = 1000 - (self.level * 50);
-
-            let tx_event = tx_event.clone();
-            thread::spawn(move || {
-                loop {
-                    thread::sleep(Duration::from_millis(level_speed.into()));
-                    tx_event.send(GameUpdate::Tick).unwrap();
-                };
-            });
+    loop {
+        display.clear_buffer();
+        render_frame(display, "High Scores", "Left/Right: change mode  Q: back");
+        display_high_scores(&high_scores, MODES[mode], None, 10, 4, display);
+        if let Some(err) = &error {
+            display.set_text(err, 10, 16, Color::Red, Color::Black);
         }
+        display.render();
 
-        // Spawn a thread which listens for keyboard input
-        {
-            let tx_event = tx_event.clone();
-            thread::spawn(move || {
-                let stdin = &mut std::io::stdin();
+        match next_key(rx_event) {
+            Key::Left | Key::Char('a') => mode = (mode + MODES.len() - 1) % MODES.len(),
+            Key::Right | Key::Char('d') => mode = (mode + 1) % MODES.len(),
+            Key::CtrlC => return Screen::Quit,
+            key if is_back_key(&key) || key == Key::Enter => return Screen::Title,
+            _ => (),
+        }
+    }
+}
 
-                loop {
-                    match get_input(stdin) {
-                        Some(k) => tx_event.send(GameUpdate::KeyPress(k)).unwrap(),
-                        None => ()
-                    }
-                }
-            });
+/// Lists the most recent replays and plays back the one the player picks.
+fn replays_screen(display: &mut Display, rx_event: &mpsc::Receiver<GameUpdate>) -> Screen {
+    let (paths, mut error) = match storage::list_replays() {
+        Ok(paths) => (paths, None),
+        Err(err) => (Vec::new(), Some(format!("Could not list replays: {}", err))),
+    };
+    let paths: Vec<PathBuf> = paths.into_iter().take(MAX_LISTED_REPLAYS).collect();
+
+    let mut items: Vec<String> = paths.iter().map(|path| {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        match storage::load_replay(path) {
+            Ok(replay) => {
+                let time = format_time(FRAME * replay.frames as u32);
+                format!("{:<16} {:<12} {}", name, replay.config.mode.to_string(), time)
+            },
+            Err(_) => format!("{:<16} (unreadable)", name),
         }
+    }).collect();
+    items.push("Back".to_string());
+    let mut menu = Menu::new(&[]);
+    menu.set_items(items);
 
-        // Main game loop. The loop listens and responds to timer and keyboard updates received on a channel
-        // as sent by the threads spawned above.
-loop {
+    loop {
         display.clear_buffer();
-        if self.game_over {
-            self.display_game_over_screen(display);
-        } else {
-            self.render(display);
+        render_frame(display, "Replays", "Arrows: move  Enter: watch  Q: back");
+        if paths.is_empty() {
+            display.set_text("No replays yet.", 12, 4, Color::Blue, Color::Black);
+        }
+        menu.render(display, 10, 5);
+        if let Some(err) = &error {
+            display.set_text(err, 10, SCREEN_HEIGHT - 2, Color::Red, Color::Black);
         }
         display.render();
 
-        match rx_event.recv() {
-            Ok(update) => {
-                match update {
-                    GameUpdate::KeyPress(key) => {
-                        if !self.game_over {
-                            match key {
-                                Key::Char('z') | Key::CtrlC => {
-                                    if self.game_over {
-                                        break;
-                                    }
-                                }
-                                k => {
-                                    if self.game_over {
-                                        // If the game is over, pressing 'R' restarts the game
-                                        if k == Key::Char('r') {
-                                            *self = Game::new(); // Restart the game
-                                            self.game_over = false;
-                                            continue;
-                                        }
-                                    } else {
-                                        self.keypress(k);
-                                    }
-                                }
-                            };
-                        }
-                    }
-                    GameUpdate::Tick => {
-                        if !self.game_over {
-                            self.advance_game();
-                        }
-                    }
+        let key = next_key(rx_event);
+        if key == Key::CtrlC {
+            return Screen::Quit;
+        }
+        if is_back_key(&key) {
+            return Screen::Title;
+        }
+        match menu.handle(&key) {
+            Some(i) if i < paths.len() => {
+                match storage::load_replay(&paths[i]) {
+                    Ok(replay) => return Screen::Watch(replay),
+                    Err(err) => error = Some(format!("Could not load replay: {}", err)),
                 }
-            }
-            Err(err) => panic!("{}", err),
+            },
+            Some(_) => return Screen::Title,
+            None => (),
         }
     }
 }
-}
 
-fn get_input(stdin: &mut std::io::Stdin) -> Option<Key> {
-    use std::io::Read;
-
-    let c = &mut [0u8];
-    match stdin.read(c) {
-        Ok(_) => {
-            match std::str::from_utf8(c) {
-                Ok("w") => Some(Key::Up),
-                Ok("a") => Some(Key::Left),
-                Ok("s") => Some(Key::Down),
-                Ok("d") => Some(Key::Right),
-                Ok(" ") => Some(Key::Space),
-                Ok("\x03") => Some(Key::CtrlC),
-                // Escape sequence started - must read two more bytes.
-                Ok("\x1b") => {
-                    let code = &mut [0u8; 2];
-                    match stdin.read(code) {
-                        Ok(_) => {
-                            match std::str::from_utf8(code) {
-                                Ok("[A") => Some(Key::Up),
-                                Ok("[B") => Some(Key::Down),
-                                Ok("[C") => Some(Key::Right),
-                                Ok("[D") => Some(Key::Left),
-                                _ => None
-                            }
-                        },
-                        Err(msg) => panic!("could not read from standard in: {}", msg)
-                    }
-                },
-                Ok(n) => Some(Key::Char(n.chars().next().unwrap())),
-                _ => None
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // The command line can skip the title screen and go straight to a replay or a game
+    let mut screen = Screen::Title;
+    if let Some(i) = args.iter().position(|arg| arg == "--replay") {
+        let path = match args.get(i + 1) {
+            Some(path) => path,
+            None => {
+                eprintln!("usage: {} --replay <file>", args[0]);
+                std::process::exit(2);
             }
-        },
-        Err(msg) => panic!("could not read from standard in: {}", msg)
+        };
+        match storage::load_replay(std::path::Path::new(path)) {
+            Ok(replay) => screen = Screen::Watch(replay),
+            Err(err) => {
+                eprintln!("could not load replay {}", err);
+                std::process::exit(1);
+            }
+        };
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--sprint") {
+        let lines = match args.get(i + 1).map(|lines| lines.parse()) {
+            Some(Ok(lines)) if SPRINT_LINES.contains(&lines) => lines,
+            None => 40,
+            _ => {
+                eprintln!("usage: {} --sprint [20|40|100]", args[0]);
+                std::process::exit(2);
+            }
+        };
+        screen = Screen::Play(Mode::Sprint { lines });
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--ultra") {
+        let time_limit = match args.get(i + 1).map(|seconds| seconds.parse()) {
+            Some(Ok(seconds)) if seconds > 0 => Duration::from_secs(seconds),
+            None => ULTRA_TIME,
+            _ => {
+                eprintln!("usage: {} --ultra [seconds]", args[0]);
+                std::process::exit(2);
+            }
+        };
+        screen = Screen::Play(Mode::Ultra { time_limit });
     }
-}
 
-fn main() {
-    let display = &mut Display::new(BOARD_WIDTH * 2 + 100, BOARD_HEIGHT + 2);
-    let game = &mut Game::new();
+    let mut bindings = match storage::load_bindings() {
+        Ok(bindings) => bindings,
+        Err(err) => {
+            eprintln!("could not load key bindings from {}", err);
+            std::process::exit(1);
+        }
+    };
 
     let _restorer = terminal::set_terminal_raw_mode();
-
-    game.play(display);
-}
\ No newline at end of file
+    let display = &mut Display::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let rx_event = &spawn_event_threads();
+
+    // The settings chosen on the settings screen, used as the template for every new game
+    let mut config = Config::default();
+    loop {
+        screen = match screen {
+            Screen::Title => title_screen(display, rx_event),
+            Screen::ModeSelect => mode_select_screen(display, rx_event),
+            Screen::Settings => settings_screen(display, rx_event, &mut config),
+            Screen::Controls => controls_screen(display, rx_event, &mut bindings),
+            Screen::HighScores => high_scores_screen(display, rx_event),
+            Screen::Replays => replays_screen(display, rx_event),
+            Screen::Play(mode) => {
+                let config = Config {
+                    seed: rand::random(),
+                    mode,
+                    ..config
+                };
+                play(&mut Recorder::new(config), display, rx_event, &bindings)
+            },
+            Screen::Watch(replay) => watch_replay(&mut Playback::new(replay), display, rx_event),
+            Screen::Quit => break,
+        };
+    }
+}
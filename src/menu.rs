@@ -0,0 +1,86 @@
+use crate::display::Display;
+use crate::input::Key;
+use tetrust::util::Color;
+
+/// The letters of the title logo, drawn side by side in different colors.
+const LOGO: [[&str; 5]; 7] = [
+    [" _____ ", "|_   _|", "  | |  ", "  | |  ", "  |_|  "],
+    [" _____ ", "| ____|", "|  _|  ", "| |___ ", "|_____|"],
+    [" _____ ", "|_   _|", "  | |  ", "  | |  ", "  |_|  "],
+    [" ____  ", "|  _ \\ ", "| |_) |", "|  _ < ", "|_| \\_\\"],
+    [" _   _ ", "| | | |", "| | | |", "| |_| |", " \\___/ "],
+    [" ____  ", "/ ___| ", "\\___ \\ ", " ___) |", "|____/ "],
+    [" _____ ", "|_   _|", "  | |  ", "  | |  ", "  |_|  "],
+];
+const LOGO_COLORS: [Color; 7] = [Color::Red, Color::Orange, Color::Cyan, Color::Green, Color::Blue, Color::Purple,
+                                 Color::Red];
+
+/// The width of the title logo in characters.
+pub const LOGO_WIDTH: u32 = 7 * 8 - 1;
+
+/// Draws the title logo with its top left corner at the given position.
+pub fn render_logo(display: &mut Display, x: u32, y: u32) {
+    for (i, letter) in LOGO.iter().enumerate() {
+        for (row, line) in letter.iter().enumerate() {
+            display.set_text(line, x + 8 * i as u32, y + row as u32, LOGO_COLORS[i], Color::Black);
+        }
+    }
+}
+
+/// A vertical list of options navigated with the arrow keys and chosen with Enter.
+pub struct Menu {
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str]) -> Menu {
+        Menu {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Replaces the labels of the options, keeping the selection. Used by menus whose labels show values.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+    }
+
+    /// Moves the selection on Up and Down, wrapping around at either end. Returns the index of the selected
+    /// option if the key chose it.
+    pub fn handle(&mut self, key: &Key) -> Option<usize> {
+        let len = self.items.len();
+        if len == 0 {
+            return None;
+        }
+
+        match key {
+            Key::Up | Key::Char('w') => self.selected = (self.selected + len - 1) % len,
+            Key::Down | Key::Char('s') => self.selected = (self.selected + 1) % len,
+            Key::Enter | Key::Space => return Some(self.selected),
+            _ => {},
+        }
+        None
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    pub fn render(&self, display: &mut Display, x: u32, y: u32) {
+        render_options(display, &self.items, self.selected, x, y);
+    }
+}
+
+/// Draws the options one per row starting at the given position, marking the selected one.
+pub fn render_options(display: &mut Display, options: &[String], selected: usize, x: u32, y: u32) {
+    for (i, option) in options.iter().enumerate() {
+        let (marker, color) = if i == selected { ("> ", Color::Orange) } else { ("  ", Color::Blue) };
+        display.set_text(&format!("{}{}", marker, option), x, y + i as u32, color, Color::Black);
+    }
+}
@@ -0,0 +1,69 @@
+//! Game modes, which decide when a game ends besides topping out.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The line goals offered for Sprint.
+pub const SPRINT_LINES: [u32; 3] = [20, 40, 100];
+/// The default time limit for Ultra.
+pub const ULTRA_TIME: Duration = Duration::from_secs(120);
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Mode {
+    /// Endless play until the player tops out.
+    Marathon,
+    /// Clear the given number of lines as fast as possible.
+    Sprint { lines: u32 },
+    /// Score as many points as possible before the time runs out.
+    Ultra { time_limit: Duration },
+}
+
+impl Mode {
+    /// Returns the number of lines that completes the game, if the mode has a line goal.
+    pub fn line_goal(&self) -> Option<u32> {
+        match self {
+            Mode::Sprint { lines } => Some(*lines),
+            _ => None,
+        }
+    }
+
+    /// Returns the time after which the game ends, if the mode has a time limit.
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self {
+            Mode::Ultra { time_limit } => Some(*time_limit),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the mode as its name followed by its parameters, such as `Sprint 40` or `Ultra 120` (in seconds).
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Marathon => write!(f, "Marathon"),
+            Mode::Sprint { lines } => write!(f, "Sprint {}", lines),
+            Mode::Ultra { time_limit } => write!(f, "Ultra {}", time_limit.as_secs()),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        let mut words = s.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("Marathon"), None) => Ok(Mode::Marathon),
+            (Some("Sprint"), Some(lines)) => {
+                let lines = lines.parse().map_err(|_| format!("invalid line goal '{}'", lines))?;
+                Ok(Mode::Sprint { lines })
+            },
+            (Some("Ultra"), Some(seconds)) => {
+                let seconds = seconds.parse().map_err(|_| format!("invalid time limit '{}'", seconds))?;
+                Ok(Mode::Ultra { time_limit: Duration::from_secs(seconds) })
+            },
+            _ => Err(format!("unknown mode '{}'", s)),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::srs::Rotation;
+use crate::util::{Color, Direction, PieceType};
+
+pub struct Piece {
+    kind: PieceType,
+    shape: Vec<Vec<u8>>,
+    rotation: Rotation,
+}
+
+impl Clone for Piece {
+    fn clone(&self) -> Piece {
+        let mut p = Piece{
+            kind: self.kind,
+            shape: Vec::with_capacity(self.shape.len()),
+            rotation: self.rotation,
+        };
+        for row in &self.shape {
+            p.shape.push(row.clone());
+        }
+        p
+    }
+}
+
+impl Piece {
+    /// Creates a new piece of the given type in its spawn orientation.
+    pub fn new(kind: PieceType) -> Piece {
+        match kind {
+            PieceType::O => Piece::new_o(),
+            PieceType::I => Piece::new_i(),
+            PieceType::T => Piece::new_t(),
+            PieceType::L => Piece::new_l(),
+            PieceType::J => Piece::new_j(),
+            PieceType::S => Piece::new_s(),
+            PieceType::Z => Piece::new_z(),
+        }
+    }
+
+    pub fn new_o() -> Piece {
+        Piece{
+            kind: PieceType::O,
+            shape: vec![vec![1, 1],
+                        vec![1, 1]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_l() -> Piece {
+        Piece{
+            kind: PieceType::L,
+            shape: vec![vec![0, 0, 1],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_j() -> Piece {
+        Piece{
+            kind: PieceType::J,
+            shape: vec![vec![1, 0, 0],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_t() -> Piece {
+        Piece{
+            kind: PieceType::T,
+            shape: vec![vec![0, 1, 0],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_s() -> Piece {
+        Piece{
+            kind: PieceType::S,
+            shape: vec![vec![0, 1, 1],
+                        vec![1, 1, 0],
+                        vec![0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_z() -> Piece {
+        Piece{
+            kind: PieceType::Z,
+            shape: vec![vec![1, 1, 0],
+                        vec![0, 1, 1],
+                        vec![0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn new_i() -> Piece {
+        Piece{
+            kind: PieceType::I,
+            shape: vec![vec![0, 0, 0, 0],
+                        vec![1, 1, 1, 1],
+                        vec![0, 0, 0, 0],
+                        vec![0, 0, 0, 0]],
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn kind(&self) -> PieceType {
+        self.kind
+    }
+
+    pub fn color(&self) -> Color {
+        self.kind.color()
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Returns the width (and height) of the square grid the shape is drawn in.
+    pub fn size(&self) -> u32 {
+        self.shape.len() as u32
+    }
+
+    /// Rotates the shape a quarter or half turn in place and updates the piece's rotation state.
+    pub fn rotate(&mut self, direction: Direction) {
+        let size = self.shape.len();
+        self.rotation = self.rotation.rotate(direction);
+
+        for row in 0..size/2 {
+            for col in row..(size - row - 1) {
+                let t = self.shape[row][col];
+
+                match direction {
+                    Direction::Left => {
+                        self.shape[row][col] = self.shape[col][size - row - 1];
+                        self.shape[col][size - row - 1] = self.shape[size - row - 1][size - col - 1];
+                        self.shape[size - row - 1][size - col - 1] = self.shape[size - col - 1][row];
+                        self.shape[size - col - 1][row] = t;
+                    },
+                    Direction::Right => {
+                        self.shape[row][col] = self.shape[size - col - 1][row];
+                        self.shape[size - col - 1][row] = self.shape[size - row - 1][size - col - 1];
+                        self.shape[size - row - 1][size - col - 1] = self.shape[col][size - row - 1];
+                        self.shape[col][size - row - 1] = t;
+                    },
+                    Direction::Flip => {
+                        self.shape[row][col] = self.shape[size - row - 1][size - col - 1];
+                        self.shape[size - row - 1][size - col - 1] = t;
+                        let t = self.shape[col][size - row - 1];
+                        self.shape[col][size - row - 1] = self.shape[size - col - 1][row];
+                        self.shape[size - col - 1][row] = t;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Calls `callback` with the row and column of each block of the piece within its grid.
+    pub fn each_point(&self, callback: &mut dyn FnMut(i32, i32)) {
+        let piece_width = self.shape.len() as i32;
+        for row in 0..piece_width {
+            for col in 0..piece_width {
+                if self.shape[row as usize][col as usize] != 0 {
+                    callback(row, col);
+                }
+            }
+        }
+    }
+}
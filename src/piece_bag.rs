@@ -0,0 +1,54 @@
+use crate::piece::Piece;
+use crate::randomizer::Randomizer;
+use std::collections::VecDeque;
+
+/// The longest preview of upcoming pieces the queue can provide.
+pub const MAX_PREVIEW: usize = 6;
+
+/// Implements a queue of randomized tetrominoes.
+///
+/// The order of the pieces is decided by a `Randomizer`, such as the 7-bag which ensures every type is used
+/// once before re-generating a new random set. The queue always holds more pieces than the longest preview,
+/// so previews can look past the end of the current bag.
+pub struct PieceBag {
+    pieces: VecDeque<Piece>,
+    randomizer: Box<dyn Randomizer>,
+}
+
+impl PieceBag {
+    pub fn new(randomizer: Box<dyn Randomizer>) -> PieceBag {
+        let mut p = PieceBag{
+            pieces: VecDeque::new(),
+            randomizer,
+        };
+        p.refill();
+        p
+    }
+
+    /// Removes and returns the next piece in the queue.
+    pub fn pop(&mut self) -> Piece {
+        let piece = self.pieces.pop_front().expect("piece bag is never empty");
+        self.refill();
+        piece
+    }
+
+    /// Returns a copy of the next piece in the queue.
+    pub fn peek(&self) -> Piece {
+        match self.pieces.front() {
+            Some(p) => p.clone(),
+            None => panic!("No next piece in piece bag")
+        }
+    }
+
+    /// Returns copies of the next `n` pieces in the queue, up to `MAX_PREVIEW`.
+    pub fn peek_n(&self, n: usize) -> Vec<Piece> {
+        self.pieces.iter().take(n.min(MAX_PREVIEW)).cloned().collect()
+    }
+
+    /// Draws new pieces until the queue holds more pieces than the longest preview.
+    fn refill(&mut self) {
+        while self.pieces.len() <= MAX_PREVIEW {
+            self.pieces.push_back(Piece::new(self.randomizer.next()));
+        }
+    }
+}
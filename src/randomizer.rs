@@ -0,0 +1,195 @@
+//! Piece generators. Every randomizer is built from an explicit seed, so a game can be replayed exactly.
+
+use crate::util::{from_debug_name, PieceType};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+
+/// Produces the stream of piece types dealt to the player.
+pub trait Randomizer {
+    fn next(&mut self) -> PieceType;
+}
+
+/// The built-in randomizers.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RandomizerKind {
+    SevenBag,
+    FourteenBag,
+    PureRandom,
+    TgmHistory,
+    Classic,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 5] = [
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::PureRandom,
+        RandomizerKind::TgmHistory,
+        RandomizerKind::Classic,
+    ];
+
+    pub fn build(&self, seed: u64) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::SevenBag => Box::new(Bag::new(seed, 1)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(seed, 2)),
+            RandomizerKind::PureRandom => Box::new(PureRandom::new(seed)),
+            RandomizerKind::TgmHistory => Box::new(TgmHistory::new(seed, 6)),
+            RandomizerKind::Classic => Box::new(Classic::new(seed)),
+        }
+    }
+}
+
+from_debug_name!(RandomizerKind, "randomizer");
+
+/// Deals shuffled bags holding `copies` of each piece type, so every type shows up `copies` times in each
+/// run of `7 * copies` pieces.
+pub struct Bag {
+    rng: ChaCha8Rng,
+    copies: usize,
+    pieces: VecDeque<PieceType>,
+}
+
+impl Bag {
+    pub fn new(seed: u64, copies: usize) -> Bag {
+        Bag {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            copies: copies.max(1),
+            pieces: VecDeque::new(),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self) -> PieceType {
+        if self.pieces.is_empty() {
+            let mut bag: Vec<PieceType> = PieceType::ALL.iter().cycle().take(7 * self.copies).copied().collect();
+            bag.shuffle(&mut self.rng);
+            self.pieces.extend(bag);
+        }
+        self.pieces.pop_front().expect("bag was just refilled")
+    }
+}
+
+/// Picks every piece independently and uniformly.
+pub struct PureRandom {
+    rng: ChaCha8Rng,
+}
+
+impl PureRandom {
+    pub fn new(seed: u64) -> PureRandom {
+        PureRandom {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Randomizer for PureRandom {
+    fn next(&mut self) -> PieceType {
+        *PieceType::ALL.choose(&mut self.rng).expect("there are seven piece types")
+    }
+}
+
+/// The Tetris The Grand Master randomizer: it remembers the last four pieces and rerolls up to `rolls` times
+/// while the pick is one of them. The history starts filled with S and Z pieces, and the very first piece is
+/// never an S, Z or O, so games don't open with an unavoidable overhang.
+pub struct TgmHistory {
+    rng: ChaCha8Rng,
+    rolls: u32,
+    history: VecDeque<PieceType>,
+    first: bool,
+}
+
+impl TgmHistory {
+    pub fn new(seed: u64, rolls: u32) -> TgmHistory {
+        TgmHistory {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            rolls: rolls.max(1),
+            history: VecDeque::from(vec![PieceType::Z, PieceType::S, PieceType::S, PieceType::Z]),
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for TgmHistory {
+    fn next(&mut self) -> PieceType {
+        let piece = if self.first {
+            self.first = false;
+            *[PieceType::I, PieceType::T, PieceType::L, PieceType::J].choose(&mut self.rng)
+                .expect("there are four opening pieces")
+        } else {
+            let mut piece = *PieceType::ALL.choose(&mut self.rng).expect("there are seven piece types");
+            for _ in 1..self.rolls {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = *PieceType::ALL.choose(&mut self.rng).expect("there are seven piece types");
+            }
+            piece
+        };
+
+        self.history.pop_front();
+        self.history.push_back(piece);
+        piece
+    }
+}
+
+/// The NES randomizer: it rolls an eight-sided die where the eighth side means "reroll", and also rerolls
+/// once if the roll repeats the previous piece. The second roll is always kept.
+pub struct Classic {
+    rng: ChaCha8Rng,
+    previous: Option<PieceType>,
+}
+
+impl Classic {
+    pub fn new(seed: u64) -> Classic {
+        Classic {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            previous: None,
+        }
+    }
+}
+
+impl Randomizer for Classic {
+    fn next(&mut self) -> PieceType {
+        let roll = self.rng.gen_range(0..8);
+        let piece = match PieceType::ALL.get(roll) {
+            Some(&piece) if Some(piece) != self.previous => piece,
+            _ => *PieceType::ALL.choose(&mut self.rng).expect("there are seven piece types"),
+        };
+
+        self.previous = Some(piece);
+        piece
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deal(randomizer: &mut dyn Randomizer, count: usize) -> Vec<PieceType> {
+        (0..count).map(|_| randomizer.next()).collect()
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_pieces() {
+        for kind in RandomizerKind::ALL {
+            let pieces = deal(&mut *kind.build(7), 100);
+            assert_eq!(deal(&mut *kind.build(7), 100), pieces, "{:?}", kind);
+            assert_ne!(deal(&mut *kind.build(8), 100), pieces, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn bags_deal_each_type_once_per_copy() {
+        for (kind, copies) in [(RandomizerKind::SevenBag, 1), (RandomizerKind::FourteenBag, 2)] {
+            let pieces = deal(&mut *kind.build(3), 7 * copies * 10);
+            for bag in pieces.chunks(7 * copies) {
+                for piece in PieceType::ALL {
+                    assert_eq!(bag.iter().filter(|&&dealt| dealt == piece).count(), copies, "{:?}", kind);
+                }
+            }
+        }
+    }
+}
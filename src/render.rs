@@ -0,0 +1,101 @@
+//! Frontend-agnostic drawing of a game.
+//!
+//! A frontend implements [`Renderer`] to put each part of the game wherever and however it likes, then calls
+//! [`render`] (or [`render_paused`]) once per frame. The engine never draws anything itself.
+
+use crate::board::Board;
+use crate::game::{ClearEvent, Game};
+use crate::mode::Mode;
+use crate::piece::Piece;
+use crate::util::Point;
+use std::time::Duration;
+
+/// How long clears such as T-spins stay announced after they happen.
+pub const ANNOUNCEMENT_TIME: Duration = Duration::from_secs(2);
+
+/// Formats a duration as minutes, seconds and milliseconds, such as `1:05.250`.
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, (millis / 1000) % 60, millis % 1000)
+}
+
+/// The numbers shown next to the board.
+#[derive(Debug, Clone)]
+pub struct Stats<'a> {
+    pub mode: Mode,
+    pub level: u32,
+    pub score: u32,
+    pub lines: u32,
+    pub time: Duration,
+    /// The game time at which every tenth line was cleared.
+    pub splits: &'a [Duration],
+}
+
+impl<'a> Stats<'a> {
+    pub fn new(game: &'a Game) -> Stats<'a> {
+        Stats {
+            mode: game.config().mode,
+            level: game.level(),
+            score: game.score(),
+            lines: game.total_lines(),
+            time: game.time(),
+            splits: game.splits(),
+        }
+    }
+}
+
+/// Things drawn on top of the game.
+#[derive(Debug, Clone)]
+pub enum Overlay<'a> {
+    /// A notable clear that just happened, such as a Tetris or a T-spin.
+    Clear(&'a ClearEvent),
+    /// The game is paused and the player picks one of the options.
+    Paused { options: &'a [String], selected: usize },
+}
+
+/// Draws the parts of a game. Positions are board coordinates: columns from the left and rows from the top,
+/// including the hidden rows.
+pub trait Renderer {
+    /// Draws the board and the pieces locked into it.
+    fn board(&mut self, board: &Board);
+
+    /// Draws the piece the player controls at its position on the board.
+    fn active_piece(&mut self, piece: &Piece, position: Point);
+
+    /// Draws the outline showing where the active piece would land if hard dropped.
+    fn ghost(&mut self, piece: &Piece, position: Point);
+
+    /// Draws the upcoming pieces, the next one first.
+    fn queue(&mut self, pieces: &[Piece]);
+
+    /// Draws the held piece, if any. `can_hold` is false while the hold has been used for the current piece.
+    fn hold(&mut self, piece: Option<&Piece>, can_hold: bool);
+
+    fn stats(&mut self, stats: &Stats);
+
+    fn overlay(&mut self, overlay: &Overlay);
+}
+
+/// Draws a running game, announcing its last clear for a little while after it happened.
+pub fn render(game: &Game, renderer: &mut dyn Renderer) {
+    renderer.board(game.board());
+    renderer.ghost(game.piece(), game.find_dropped_position());
+    renderer.active_piece(game.piece(), game.piece_position());
+    renderer.queue(&game.next_pieces());
+    renderer.hold(game.held_piece(), game.can_hold());
+    renderer.stats(&Stats::new(game));
+
+    if let Some(clear) = game.last_clear() {
+        if game.time() - clear.time < ANNOUNCEMENT_TIME {
+            renderer.overlay(&Overlay::Clear(clear));
+        }
+    }
+}
+
+/// Draws a paused game with an empty board and no pieces, so pausing can't be used to plan ahead, and the
+/// pause options on top.
+pub fn render_paused(game: &Game, options: &[String], selected: usize, renderer: &mut dyn Renderer) {
+    renderer.board(&Board::new());
+    renderer.stats(&Stats::new(game));
+    renderer.overlay(&Overlay::Paused { options, selected });
+}
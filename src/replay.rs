@@ -0,0 +1,264 @@
+//! Recording and re-simulating games.
+//!
+//! The engine is deterministic, so a game is fully described by its config (which includes the seed) and the
+//! inputs applied before each frame. Both recording and playback advance the game in fixed `FRAME` steps, so
+//! a replay re-runs the exact same sequence of `Game::apply`, `Game::press`, `Game::repeat`, `Game::release`
+//! and `Game::step` calls.
+
+use crate::game::{Config, Game, Input, FRAME};
+use crate::util::parse_number;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+const HEADER: &str = "tetrust-replay 1";
+
+/// How an input reached the game: tapped once with `Game::apply`, or held between `Game::press` and
+/// `Game::release`. A tap can also become held with `Game::repeat`, given how long ago its key went down.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum InputEvent {
+    Tap,
+    Press,
+    Repeat(Duration),
+    Release,
+}
+
+impl InputEvent {
+    /// Returns the word starting the event's lines in a replay file.
+    fn keyword(&self) -> &'static str {
+        match self {
+            InputEvent::Tap => "input",
+            InputEvent::Press => "press",
+            InputEvent::Repeat(_) => "repeat",
+            InputEvent::Release => "release",
+        }
+    }
+}
+
+/// An input applied just before the given frame was stepped.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ReplayInput {
+    pub frame: u64,
+    pub input: Input,
+    pub event: InputEvent,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub config: Config,
+    pub inputs: Vec<ReplayInput>,
+    /// The total number of frames the game ran for.
+    pub frames: u64,
+}
+
+impl Replay {
+    pub fn new(config: Config) -> Replay {
+        Replay {
+            config,
+            inputs: Vec::new(),
+            frames: 0,
+        }
+    }
+}
+
+/// Writes the replay as a plain text file: a header, one `key value` line per config setting, the frame
+/// count, then one `input <frame> <name>` line per tapped input, or `press`, `repeat` and `release` lines for
+/// held ones. A `repeat` line ends with how long ago the key went down, in milliseconds.
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "seed {}", self.config.seed)?;
+        writeln!(f, "mode {}", self.config.mode)?;
+        writeln!(f, "lock_delay_ms {}", self.config.lock_delay.as_millis())?;
+        writeln!(f, "max_lock_resets {}", self.config.max_lock_resets)?;
+        writeln!(f, "scoring {:?}", self.config.scoring)?;
+        writeln!(f, "randomizer {:?}", self.config.randomizer)?;
+        writeln!(f, "next_count {}", self.config.next_count)?;
+        writeln!(f, "das_ms {}", self.config.das.as_millis())?;
+        writeln!(f, "arr_ms {}", self.config.arr.as_millis())?;
+        writeln!(f, "soft_drop_factor {}", self.config.soft_drop_factor)?;
+        writeln!(f, "flip_kicks {:?}", self.config.flip_kicks)?;
+        writeln!(f, "frames {}", self.frames)?;
+        for input in &self.inputs {
+            write!(f, "{} {} {:?}", input.event.keyword(), input.frame, input.input)?;
+            if let InputEvent::Repeat(held_for) = input.event {
+                write!(f, " {}", held_for.as_millis())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Replay, String> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a tetrust replay".to_string());
+        }
+
+        let mut replay = Replay::new(Config::default());
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once(' ').ok_or_else(|| format!("missing value in '{}'", line))?;
+
+            let config = &mut replay.config;
+            match key {
+                "seed" => config.seed = parse_number(value)?,
+                "mode" => config.mode = value.parse()?,
+                "lock_delay_ms" => config.lock_delay = Duration::from_millis(parse_number(value)?),
+                "max_lock_resets" => config.max_lock_resets = parse_number(value)?,
+                "scoring" => config.scoring = value.parse()?,
+                "randomizer" => config.randomizer = value.parse()?,
+                "next_count" => config.next_count = parse_number(value)?,
+                "das_ms" => config.das = Duration::from_millis(parse_number(value)?),
+                "arr_ms" => config.arr = Duration::from_millis(parse_number(value)?),
+                "soft_drop_factor" => config.soft_drop_factor = parse_number(value)?,
+                "flip_kicks" => config.flip_kicks = value.parse()?,
+                "frames" => replay.frames = parse_number(value)?,
+                "input" | "press" | "repeat" | "release" => {
+                    let (frame, mut input) = value.split_once(' ').ok_or_else(|| format!("missing input in '{}'", line))?;
+                    let frame = parse_number(frame)?;
+                    let event = match key {
+                        "input" => InputEvent::Tap,
+                        "press" => InputEvent::Press,
+                        "repeat" => {
+                            let (name, held_for) = input.split_once(' ')
+                                .ok_or_else(|| format!("missing hold time in '{}'", line))?;
+                            input = name;
+                            InputEvent::Repeat(Duration::from_millis(parse_number(held_for)?))
+                        },
+                        _ => InputEvent::Release,
+                    };
+                    replay.inputs.push(ReplayInput { frame, input: input.parse()?, event });
+                },
+                _ => return Err(format!("unknown replay line '{}'", line)),
+            }
+        }
+
+        Ok(replay)
+    }
+}
+
+/// Wraps a game and records every input and frame played on it.
+pub struct Recorder {
+    game: Game,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn new(config: Config) -> Recorder {
+        Recorder {
+            game: Game::new(config),
+            replay: Replay::new(config),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Applies an input to the game, recording it against the upcoming frame.
+    pub fn apply(&mut self, input: Input) {
+        self.record(input, InputEvent::Tap);
+        self.game.apply(input);
+    }
+
+    /// Starts holding an input, recording it against the upcoming frame.
+    pub fn press(&mut self, input: Input) {
+        self.record(input, InputEvent::Press);
+        self.game.press(input);
+    }
+
+    /// Starts holding an input that was already tapped, its key having gone down `held_for` ago, recording it
+    /// against the upcoming frame. The time is recorded to the millisecond, so it is rounded down to that first.
+    pub fn repeat(&mut self, input: Input, held_for: Duration) {
+        let held_for = Duration::from_millis(held_for.as_millis() as u64);
+        self.record(input, InputEvent::Repeat(held_for));
+        self.game.repeat(input, held_for);
+    }
+
+    /// Stops holding an input, recording it against the upcoming frame.
+    pub fn release(&mut self, input: Input) {
+        self.record(input, InputEvent::Release);
+        self.game.release(input);
+    }
+
+    fn record(&mut self, input: Input, event: InputEvent) {
+        if !self.game.is_game_over() {
+            self.replay.inputs.push(ReplayInput { frame: self.replay.frames, input, event });
+        }
+    }
+
+    /// Advances the game by one frame.
+    pub fn step(&mut self) {
+        if !self.game.is_game_over() {
+            self.replay.frames += 1;
+        }
+        self.game.step(FRAME);
+    }
+}
+
+/// Re-simulates a recorded game one frame at a time.
+pub struct Playback {
+    game: Game,
+    replay: Replay,
+    frame: u64,
+    next_input: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            game: Game::new(replay.config),
+            replay,
+            frame: 0,
+            next_input: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the number of frames played back so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns true once every recorded frame and input has been played back.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.replay.frames && self.next_input >= self.replay.inputs.len()
+    }
+
+    /// Applies the inputs recorded for the current frame, then steps the game by one frame. Inputs recorded
+    /// after the last frame, such as the hard drop that ended the game, are applied without a step.
+    pub fn step(&mut self) {
+        while let Some(input) = self.replay.inputs.get(self.next_input) {
+            if input.frame > self.frame {
+                break;
+            }
+            match input.event {
+                InputEvent::Tap => self.game.apply(input.input),
+                InputEvent::Press => self.game.press(input.input),
+                InputEvent::Repeat(held_for) => self.game.repeat(input.input, held_for),
+                InputEvent::Release => self.game.release(input.input),
+            }
+            self.next_input += 1;
+        }
+
+        if self.frame < self.replay.frames {
+            self.game.step(FRAME);
+            self.frame += 1;
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Scoring models, which turn locks and drops into points.
+
+use crate::game::TSpin;
+use crate::util::from_debug_name;
+
+/// The points awarded for a lock, along with the streaks it took part in.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Award {
+    pub points: u32,
+    /// True if this clear continued a back-to-back chain of difficult clears.
+    pub back_to_back: bool,
+    /// The number of consecutive clearing locks before this one, or 0 if this clear started a new combo.
+    pub combo: u32,
+}
+
+/// Decides how many points the player earns. Models may keep state between locks, for example to track
+/// back-to-back chains and combos.
+pub trait ScoringModel {
+    /// Called every time a piece locks, whether or not it cleared any lines. `perfect_clear` is true if the
+    /// clear left the board completely empty.
+    fn lock(&mut self, lines: u32, t_spin: Option<TSpin>, perfect_clear: bool, level: u32) -> Award;
+
+    /// Called when the player soft drops the piece by the given number of cells.
+    fn soft_drop(&mut self, cells: u32) -> u32;
+
+    /// Called when the player hard drops the piece by the given number of cells.
+    fn hard_drop(&mut self, cells: u32) -> u32;
+}
+
+/// The built-in scoring models.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Scoring {
+    Guideline,
+    Classic,
+}
+
+impl Scoring {
+    pub const ALL: [Scoring; 2] = [Scoring::Guideline, Scoring::Classic];
+
+    pub fn model(&self) -> Box<dyn ScoringModel> {
+        match self {
+            Scoring::Guideline => Box::new(GuidelineScoring::new()),
+            Scoring::Classic => Box::new(ClassicScoring),
+        }
+    }
+}
+
+from_debug_name!(Scoring, "scoring model");
+
+/// The modern guideline scoring system: line clears and T-spins are multiplied by the level, difficult
+/// clears (Tetrises and T-spins that clear lines) earn 1.5x while chained back-to-back, consecutive
+/// clearing locks build a combo worth 50 points per step, and emptying the board earns a perfect clear bonus.
+pub struct GuidelineScoring {
+    back_to_back: bool,
+    /// The current combo, or `None` if the last lock cleared nothing.
+    combo: Option<u32>,
+}
+
+impl Default for GuidelineScoring {
+    fn default() -> GuidelineScoring {
+        GuidelineScoring::new()
+    }
+}
+
+impl GuidelineScoring {
+    pub fn new() -> GuidelineScoring {
+        GuidelineScoring {
+            back_to_back: false,
+            combo: None,
+        }
+    }
+}
+
+impl ScoringModel for GuidelineScoring {
+    fn lock(&mut self, lines: u32, t_spin: Option<TSpin>, perfect_clear: bool, level: u32) -> Award {
+        let base = match (t_spin, lines) {
+            (Some(TSpin::Full), 0) => 400,
+            (Some(TSpin::Full), 1) => 800,
+            (Some(TSpin::Full), 2) => 1200,
+            (Some(TSpin::Full), _) => 1600,
+            (Some(TSpin::Mini), 0) => 100,
+            (Some(TSpin::Mini), 1) => 200,
+            (Some(TSpin::Mini), _) => 400,
+            (None, 0) => 0,
+            (None, 1) => 100,
+            (None, 2) => 300,
+            (None, 3) => 500,
+            (None, _) => 800,
+        };
+
+        if lines == 0 {
+            // T-spins without lines neither continue nor break a back-to-back chain
+            self.combo = None;
+            return Award {
+                points: base * level,
+                back_to_back: false,
+                combo: 0,
+            };
+        }
+
+        let difficult = lines >= 4 || t_spin.is_some();
+        let back_to_back = difficult && self.back_to_back;
+        self.back_to_back = difficult;
+
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+
+        let mut points = base * level;
+        if back_to_back {
+            points += points / 2;
+        }
+        points += 50 * combo * level;
+
+        if perfect_clear {
+            points += level * match lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if back_to_back => 3200,
+                _ => 2000,
+            };
+        }
+
+        Award {
+            points,
+            back_to_back,
+            combo,
+        }
+    }
+
+    fn soft_drop(&mut self, cells: u32) -> u32 {
+        cells
+    }
+
+    fn hard_drop(&mut self, cells: u32) -> u32 {
+        cells * 2
+    }
+}
+
+/// The original NES scoring: 40/100/300/1200 points for 1-4 lines times the level (the NES's level plus one, as
+/// its levels start at 0), one point per soft dropped cell, and nothing for T-spins, perfect clears or hard drops.
+pub struct ClassicScoring;
+
+impl ScoringModel for ClassicScoring {
+    fn lock(&mut self, lines: u32, _t_spin: Option<TSpin>, _perfect_clear: bool, level: u32) -> Award {
+        let base = match lines {
+            0 => 0,
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            _ => 1200,
+        };
+
+        Award {
+            points: base * level,
+            back_to_back: false,
+            combo: 0,
+        }
+    }
+
+    fn soft_drop(&mut self, cells: u32) -> u32 {
+        cells
+    }
+
+    fn hard_drop(&mut self, _cells: u32) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_to_back_difficult_clears_earn_half_again() {
+        let mut scoring = GuidelineScoring::new();
+        assert_eq!(scoring.lock(4, None, false, 1), Award { points: 800, back_to_back: false, combo: 0 });
+        // A lock without lines ends the combo but not the chain
+        scoring.lock(0, None, false, 1);
+        assert_eq!(scoring.lock(4, None, false, 1), Award { points: 1200, back_to_back: true, combo: 0 });
+        scoring.lock(0, None, false, 1);
+        assert_eq!(scoring.lock(2, Some(TSpin::Full), false, 2), Award { points: 3600, back_to_back: true, combo: 0 });
+
+        // An easy clear breaks the chain
+        scoring.lock(0, None, false, 1);
+        assert!(!scoring.lock(1, None, false, 1).back_to_back);
+        scoring.lock(0, None, false, 1);
+        assert!(!scoring.lock(4, None, false, 1).back_to_back);
+    }
+
+    #[test]
+    fn combos_earn_fifty_per_step_and_level() {
+        let mut scoring = GuidelineScoring::new();
+        assert_eq!(scoring.lock(1, None, false, 2), Award { points: 200, back_to_back: false, combo: 0 });
+        assert_eq!(scoring.lock(1, None, false, 2), Award { points: 300, back_to_back: false, combo: 1 });
+        assert_eq!(scoring.lock(2, None, false, 2), Award { points: 800, back_to_back: false, combo: 2 });
+        scoring.lock(0, None, false, 2);
+        assert_eq!(scoring.lock(1, None, false, 2).combo, 0);
+    }
+
+    #[test]
+    fn zero_line_t_spins_leave_back_to_back_alone_but_end_combos() {
+        let mut scoring = GuidelineScoring::new();
+        scoring.lock(4, None, false, 1);
+        assert_eq!(scoring.lock(0, Some(TSpin::Full), false, 1), Award { points: 400, back_to_back: false, combo: 0 });
+        assert_eq!(scoring.lock(1, Some(TSpin::Mini), false, 1), Award { points: 300, back_to_back: true, combo: 0 });
+
+        // Nor do they start a chain
+        let mut scoring = GuidelineScoring::new();
+        scoring.lock(0, Some(TSpin::Full), false, 1);
+        assert!(!scoring.lock(4, None, false, 1).back_to_back);
+    }
+
+    #[test]
+    fn perfect_clears_earn_a_bonus_by_lines() {
+        let points: Vec<u32> = (1..=4).map(|lines| GuidelineScoring::new().lock(lines, None, true, 1).points).collect();
+        assert_eq!(points, [100 + 800, 300 + 1200, 500 + 1800, 800 + 2000]);
+        assert_eq!(GuidelineScoring::new().lock(2, None, true, 3).points, (300 + 1200) * 3);
+
+        let mut scoring = GuidelineScoring::new();
+        scoring.lock(4, None, false, 1);
+        scoring.lock(0, None, false, 1);
+        assert_eq!(scoring.lock(4, None, true, 1), Award { points: 1200 + 3200, back_to_back: true, combo: 0 });
+    }
+
+    #[test]
+    fn classic_scoring_multiplies_the_nes_table_by_the_level() {
+        let mut scoring = ClassicScoring;
+        let points: Vec<u32> = (0..=4).map(|lines| scoring.lock(lines, None, false, 1).points).collect();
+        assert_eq!(points, [0, 40, 100, 300, 1200]);
+        assert_eq!(scoring.lock(4, Some(TSpin::Full), true, 3).points, 3600);
+        assert_eq!(scoring.lock(2, None, false, 10).points, 1000);
+    }
+}
@@ -0,0 +1,177 @@
+//! Super Rotation System (SRS) rotation states and wall-kick tables.
+//!
+//! See https://tetris.wiki/Super_Rotation_System for the reference tables. Offsets are given as in the
+//! guideline, with `x` pointing right and `y` pointing *up*, so callers must negate `y` to get board
+//! coordinates. The guideline has no 180° rotation, so its kicks come from one of the [`FlipKicks`] tables
+//! other games use.
+
+use crate::board::Board;
+use crate::piece::Piece;
+use crate::util::{from_debug_name, Direction, PieceType, Point};
+
+/// The four rotation states of a piece: spawn (0), clockwise (R), 180° (2) and counter-clockwise (L).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Rotation {
+    Spawn,
+    Right,
+    Flip,
+    Left,
+}
+
+impl Rotation {
+    /// Returns the rotation state reached by turning once in the given direction.
+    pub fn rotate(self, direction: Direction) -> Rotation {
+        match (self, direction) {
+            (Rotation::Spawn, Direction::Flip) => Rotation::Flip,
+            (Rotation::Right, Direction::Flip) => Rotation::Left,
+            (Rotation::Flip, Direction::Flip) => Rotation::Spawn,
+            (Rotation::Left, Direction::Flip) => Rotation::Right,
+            (Rotation::Spawn, Direction::Right) => Rotation::Right,
+            (Rotation::Right, Direction::Right) => Rotation::Flip,
+            (Rotation::Flip, Direction::Right) => Rotation::Left,
+            (Rotation::Left, Direction::Right) => Rotation::Spawn,
+            (Rotation::Spawn, Direction::Left) => Rotation::Left,
+            (Rotation::Left, Direction::Left) => Rotation::Flip,
+            (Rotation::Flip, Direction::Left) => Rotation::Right,
+            (Rotation::Right, Direction::Left) => Rotation::Spawn,
+        }
+    }
+}
+
+type KickTable = [[(i32, i32); 5]; 8];
+
+/// Kicks for the J, L, S, T and Z pieces, in the order 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L.
+const JLSTZ_KICKS: KickTable = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+];
+
+/// Kicks for the I piece, in the same order as `JLSTZ_KICKS`.
+const I_KICKS: KickTable = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+];
+
+/// The O piece never kicks: it only has to fit in place.
+const NO_KICKS: [(i32, i32); 1] = [(0, 0)];
+
+/// The kick tables for 180° rotations.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FlipKicks {
+    /// The piece only turns if it fits in place.
+    None,
+    /// TETR.IO's SRS+, with the same kicks for every piece.
+    SrsPlus,
+    /// Nullpomino's, with separate and longer lists of kicks for the I piece and the others.
+    Nullpomino,
+}
+
+impl FlipKicks {
+    pub const ALL: [FlipKicks; 3] = [
+        FlipKicks::None,
+        FlipKicks::SrsPlus,
+        FlipKicks::Nullpomino,
+    ];
+}
+
+from_debug_name!(FlipKicks, "180 kicks");
+
+/// SRS+ kicks for every piece, in the order 0->2, R->L, 2->0, L->R.
+const SRS_PLUS_FLIP_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+];
+
+/// Nullpomino kicks for the J, L, S, T and Z pieces, in the same order as `SRS_PLUS_FLIP_KICKS`.
+const NULLPOMINO_FLIP_KICKS: [[(i32, i32); 12]; 4] = [
+    [(0, 0), (1, 0), (2, 0), (1, 1), (2, 1), (-1, 0), (-2, 0), (-1, 1), (-2, 1), (0, -1), (3, 0), (-3, 0)],
+    [(0, 0), (0, 1), (0, 2), (-1, 1), (-1, 2), (0, -1), (0, -2), (-1, -1), (-1, -2), (1, 0), (0, 3), (0, -3)],
+    [(0, 0), (-1, 0), (-2, 0), (-1, -1), (-2, -1), (1, 0), (2, 0), (1, -1), (2, -1), (0, 1), (-3, 0), (3, 0)],
+    [(0, 0), (0, 1), (0, 2), (1, 1), (1, 2), (0, -1), (0, -2), (1, -1), (1, -2), (-1, 0), (0, 3), (0, -3)],
+];
+
+/// Nullpomino kicks for the I piece, in the same order as `SRS_PLUS_FLIP_KICKS`.
+const NULLPOMINO_I_FLIP_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (-1, 0), (-2, 0), (1, 0), (2, 0), (0, 1)],
+    [(0, 0), (0, 1), (0, 2), (0, -1), (0, -2), (-1, 0)],
+    [(0, 0), (1, 0), (2, 0), (-1, 0), (-2, 0), (0, -1)],
+    [(0, 0), (0, 1), (0, 2), (0, -1), (0, -2), (1, 0)],
+];
+
+/// Returns the ordered list of offsets to try when turning a piece of the given type 180° from a state.
+pub fn flip_kicks(table: FlipKicks, kind: PieceType, from: Rotation) -> &'static [(i32, i32)] {
+    let index = match from {
+        Rotation::Spawn => 0,
+        Rotation::Right => 1,
+        Rotation::Flip => 2,
+        Rotation::Left => 3,
+    };
+    match (table, kind) {
+        (FlipKicks::None, _) | (_, PieceType::O) => &NO_KICKS,
+        (FlipKicks::SrsPlus, _) => &SRS_PLUS_FLIP_KICKS[index],
+        (FlipKicks::Nullpomino, PieceType::I) => &NULLPOMINO_I_FLIP_KICKS[index],
+        (FlipKicks::Nullpomino, _) => &NULLPOMINO_FLIP_KICKS[index],
+    }
+}
+
+/// Returns the ordered list of offsets to try when rotating a piece of the given type between two states.
+pub fn kicks(kind: PieceType, from: Rotation, to: Rotation) -> &'static [(i32, i32)] {
+    let table = match kind {
+        PieceType::O => return &NO_KICKS,
+        PieceType::I => &I_KICKS,
+        _ => &JLSTZ_KICKS,
+    };
+
+    let index = match (from, to) {
+        (Rotation::Spawn, Rotation::Right) => 0,
+        (Rotation::Right, Rotation::Spawn) => 1,
+        (Rotation::Right, Rotation::Flip) => 2,
+        (Rotation::Flip, Rotation::Right) => 3,
+        (Rotation::Flip, Rotation::Left) => 4,
+        (Rotation::Left, Rotation::Flip) => 5,
+        (Rotation::Left, Rotation::Spawn) => 6,
+        (Rotation::Spawn, Rotation::Left) => 7,
+        _ => return &NO_KICKS,
+    };
+    &table[index]
+}
+
+/// Rotates `piece`, positioned at `origin`, in the given direction, trying each kick in turn until the rotated
+/// piece fits on the board. Half turns kick following `flip_kicks`. Returns the rotated piece, its new position
+/// and the index of the kick that was used, or `None` if no kick fits.
+pub fn rotate(board: &Board, piece: &Piece, origin: Point, direction: Direction,
+              flip_kicks: FlipKicks) -> Option<(Piece, Point, usize)> {
+    let mut new_piece = piece.clone();
+    new_piece.rotate(direction);
+
+    let kicks = match direction {
+        Direction::Flip => self::flip_kicks(flip_kicks, piece.kind(), piece.rotation()),
+        _ => kicks(piece.kind(), piece.rotation(), new_piece.rotation()),
+    };
+    for (kick, &(dx, dy)) in kicks.iter().enumerate() {
+        // Kick offsets point up, while board rows grow downwards.
+        let new_origin = Point{
+            x: origin.x + dx,
+            y: origin.y - dy,
+        };
+        if !board.collision_test(&new_piece, new_origin) {
+            return Some((new_piece, new_origin, kick));
+        }
+    }
+
+    None
+}
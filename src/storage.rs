@@ -0,0 +1,128 @@
+use crate::bindings::Bindings;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tetrust::high_score::HighScores;
+use tetrust::replay::Replay;
+
+/// Returns the directory tetrust keeps its files in: `$XDG_DATA_HOME/tetrust`, falling back to
+/// `~/.local/share/tetrust` as the XDG spec suggests.
+pub fn data_dir() -> PathBuf {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").unwrap_or_default();
+            Path::new(&home).join(".local").join("share")
+        }
+    };
+    base.join("tetrust")
+}
+
+/// Returns the directory tetrust keeps its settings in: `$XDG_CONFIG_HOME/tetrust`, falling back to
+/// `~/.config/tetrust`.
+pub fn config_dir() -> PathBuf {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").unwrap_or_default();
+            Path::new(&home).join(".config")
+        }
+    };
+    base.join("tetrust")
+}
+
+/// Replaces the file's contents by writing them to a temporary file next to it, then renaming that over the
+/// file, so a crash halfway through leaves either the old contents or the new ones.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+pub fn replays_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+/// Writes the replay to a new, timestamped file in the replays directory and returns its path.
+pub fn save_replay(replay: &Replay) -> io::Result<PathBuf> {
+    let dir = replays_dir();
+    fs::create_dir_all(&dir)?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = dir.join(format!("{}-{:03}.replay", now.as_secs(), now.subsec_millis()));
+    fs::write(&path, replay.to_string())?;
+    Ok(path)
+}
+
+pub fn load_replay(path: &Path) -> Result<Replay, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    text.parse().map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+pub fn high_scores_path() -> PathBuf {
+    data_dir().join("highscores")
+}
+
+/// Reads the high score tables, which are empty until the first record is saved.
+pub fn load_high_scores() -> Result<HighScores, String> {
+    let path = high_scores_path();
+    match fs::read_to_string(&path) {
+        Ok(text) => text.parse().map_err(|err| format!("{}: {}", path.display(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HighScores::new()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+pub fn save_high_scores(high_scores: &HighScores) -> io::Result<()> {
+    fs::create_dir_all(data_dir())?;
+    write_atomically(&high_scores_path(), &high_scores.to_string())
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Returns the paths of the saved replays, newest first.
+pub fn list_replays() -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    match fs::read_dir(replays_dir()) {
+        Ok(entries) => {
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|extension| extension == "replay") {
+                    paths.push(path);
+                }
+            }
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {},
+        Err(err) => return Err(err),
+    }
+
+    // Replay names start with the time they were saved at
+    paths.sort();
+    paths.reverse();
+    Ok(paths)
+}
+
+pub fn bindings_path() -> PathBuf {
+    config_dir().join("keys.toml")
+}
+
+/// Reads the key bindings, which are the default ones until the player changes them.
+pub fn load_bindings() -> Result<Bindings, String> {
+    let path = bindings_path();
+    match fs::read_to_string(&path) {
+        Ok(text) => text.parse().map_err(|err| format!("{}: {}", path.display(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Bindings::default()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+pub fn save_bindings(bindings: &Bindings) -> io::Result<()> {
+    fs::create_dir_all(config_dir())?;
+    write_atomically(&bindings_path(), &bindings.to_string())
+}
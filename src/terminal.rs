@@ -22,7 +22,7 @@ struct termios {
     c_ospeed: c_ulong,        // output speed
 }
 
-extern {
+extern "C" {
     fn tcgetattr(filedes: c_int, termptr: *mut termios) -> c_int;
     fn tcsetattr(filedes: c_int, opt: c_int, termptr: *const termios) -> c_int;
     fn cfmakeraw(termptr: *mut termios);
@@ -43,7 +43,7 @@ fn get_terminal_attr() -> (termios, c_int) {
         // first parameter is file descriptor number, 0 ==> standard input
         let err = tcgetattr(0, ios as *mut termios);
 
-        return (*ios, err);
+        (*ios, err)
     }
 }
 
@@ -51,7 +51,7 @@ fn make_raw(ios: &termios) -> termios {
     unsafe {
         let mut ios = *ios;
         cfmakeraw(&mut ios);
-        return ios;
+        ios
     }
 }
 
@@ -59,7 +59,7 @@ fn set_terminal_attr(ios: &termios) -> c_int {
     unsafe {
         // first paramter is file descriptor number, 0 ==> standard input
         // second paramter is when to set, 0 ==> now
-        return tcsetattr(0, 0, ios as *const termios);
+        tcsetattr(0, 0, ios as *const termios)
     }
 }
 
@@ -0,0 +1,141 @@
+//! A headless renderer that draws games as plain text, for tests and tools without a terminal.
+//!
+//! A frame is a stats line, the hold slot and the next queue, then the board one line per row from the top
+//! (hidden rows included) and finally any overlays. On the board `.` is empty, capital letters are locked blocks
+//! of that piece type, `#` is garbage, lowercase letters are the active piece and `*` is its ghost. Locked blocks
+//! and garbage match `Board`'s `Display` format, but the active piece and ghost don't parse back with `FromStr`.
+
+use crate::board::{Board, BOARD_HEIGHT, BOARD_WIDTH};
+use crate::game::Game;
+use crate::piece::Piece;
+use crate::render::{self, format_time, Overlay, Renderer, Stats};
+use crate::util::Point;
+use std::fmt;
+
+/// Collects the parts of a frame as text. Print it with `Display` once the game has been rendered into it.
+pub struct TextRenderer {
+    cells: [[char; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize],
+    stats: Vec<String>,
+    hold: Option<String>,
+    queue: Option<String>,
+    overlays: Vec<String>,
+}
+
+impl Default for TextRenderer {
+    fn default() -> TextRenderer {
+        TextRenderer::new()
+    }
+}
+
+impl TextRenderer {
+    pub fn new() -> TextRenderer {
+        TextRenderer {
+            cells: [['.'; BOARD_WIDTH as usize]; BOARD_HEIGHT as usize],
+            stats: Vec::new(),
+            hold: None,
+            queue: None,
+            overlays: Vec::new(),
+        }
+    }
+
+    fn draw_piece(&mut self, piece: &Piece, origin: Point, c: char) {
+        let cells = &mut self.cells;
+        piece.each_point(&mut |row, col| {
+            let (x, y) = (origin.x + col, origin.y + row);
+            if (0..BOARD_WIDTH as i32).contains(&x) && (0..BOARD_HEIGHT as i32).contains(&y) {
+                cells[y as usize][x as usize] = c;
+            }
+        });
+    }
+}
+
+/// Renders a running game as a text frame.
+pub fn render_text(game: &Game) -> String {
+    let mut renderer = TextRenderer::new();
+    render::render(game, &mut renderer);
+    renderer.to_string()
+}
+
+fn letters(pieces: &[Piece]) -> String {
+    let letters: Vec<String> = pieces.iter().map(|piece| piece.kind().letter().to_string()).collect();
+    letters.join(" ")
+}
+
+impl Renderer for TextRenderer {
+    fn board(&mut self, board: &Board) {
+        for (row, line) in board.to_string().lines().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                self.cells[row][col] = c;
+            }
+        }
+    }
+
+    fn active_piece(&mut self, piece: &Piece, position: Point) {
+        self.draw_piece(piece, position, piece.kind().letter().to_ascii_lowercase());
+    }
+
+    fn ghost(&mut self, piece: &Piece, position: Point) {
+        self.draw_piece(piece, position, '*');
+    }
+
+    fn queue(&mut self, pieces: &[Piece]) {
+        self.queue = Some(format!("Next: {}", letters(pieces)));
+    }
+
+    fn hold(&mut self, piece: Option<&Piece>, can_hold: bool) {
+        let held = piece.map_or("-".to_string(), |piece| piece.kind().letter().to_string());
+        let used = if can_hold { "" } else { " (used)" };
+        self.hold = Some(format!("Hold: {}{}", held, used));
+    }
+
+    fn stats(&mut self, stats: &Stats) {
+        self.stats.push(format!(
+            "{}  Score: {}  Level: {}  Lines: {}  Time: {}",
+            stats.mode, stats.score, stats.level, stats.lines, format_time(stats.time),
+        ));
+        if !stats.splits.is_empty() {
+            let splits: Vec<String> = stats.splits.iter().map(|split| format_time(*split)).collect();
+            self.stats.push(format!("Splits: {}", splits.join(" ")));
+        }
+    }
+
+    fn overlay(&mut self, overlay: &Overlay) {
+        match overlay {
+            Overlay::Clear(clear) => {
+                let mut line = format!("{} +{}", clear.description(), clear.points);
+                if clear.back_to_back {
+                    line.push_str("  Back-to-Back");
+                }
+                if clear.combo > 0 {
+                    line.push_str(&format!("  {} Combo", clear.combo));
+                }
+                if clear.perfect_clear {
+                    line.push_str("  Perfect Clear");
+                }
+                self.overlays.push(line);
+            },
+            Overlay::Paused { options, selected } => {
+                self.overlays.push("PAUSED".to_string());
+                for (i, option) in options.iter().enumerate() {
+                    let marker = if i == *selected { "> " } else { "  " };
+                    self.overlays.push(format!("{}{}", marker, option));
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for TextRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.stats.iter().chain(&self.hold).chain(&self.queue) {
+            writeln!(f, "{}", line)?;
+        }
+        for row in &self.cells {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        for line in &self.overlays {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
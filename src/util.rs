@@ -1,3 +1,6 @@
+use std::num::ParseIntError;
+use std::str::FromStr;
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Color {
     Black,
@@ -7,10 +10,99 @@ pub enum Color {
     Red,
     Blue,
     Orange,
+    Grey,
 }
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum Direction {
     Left,
-    Right
+    Right,
+    /// A half turn, 180 degrees.
+    Flip,
+}
+
+/// The seven tetromino types.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PieceType {
+    O,
+    I,
+    T,
+    L,
+    J,
+    S,
+    Z,
+}
+
+impl PieceType {
+    /// Returns the position of the type in `PieceType::ALL`.
+    pub fn index(&self) -> usize {
+        PieceType::ALL.iter().position(|kind| kind == self).expect("ALL lists every piece type")
+    }
+
+    /// Returns the color the piece type is drawn in.
+    pub fn color(&self) -> Color {
+        match self {
+            PieceType::O | PieceType::I => Color::Cyan,
+            PieceType::T => Color::Purple,
+            PieceType::L => Color::Orange,
+            PieceType::J => Color::Blue,
+            PieceType::S => Color::Green,
+            PieceType::Z => Color::Red,
+        }
+    }
+
+    /// Returns the letter naming the piece type, such as `'T'`.
+    pub fn letter(&self) -> char {
+        match self {
+            PieceType::O => 'O',
+            PieceType::I => 'I',
+            PieceType::T => 'T',
+            PieceType::L => 'L',
+            PieceType::J => 'J',
+            PieceType::S => 'S',
+            PieceType::Z => 'Z',
+        }
+    }
+
+    /// Returns the piece type named by the letter, in either case.
+    pub fn from_letter(letter: char) -> Option<PieceType> {
+        PieceType::ALL.iter().find(|kind| kind.letter() == letter.to_ascii_uppercase()).copied()
+    }
+
+    pub const ALL: [PieceType; 7] = [
+        PieceType::O,
+        PieceType::L,
+        PieceType::J,
+        PieceType::T,
+        PieceType::S,
+        PieceType::Z,
+        PieceType::I,
+    ];
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Implements `FromStr` for an enum with an `ALL` array, parsing the variant names printed by its `Debug`
+/// implementation. Errors describe the type as given, as in "unknown randomizer 'Foo'".
+macro_rules! from_debug_name {
+    ($type:ident, $description:literal) => {
+        impl std::str::FromStr for $type {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<$type, String> {
+                $type::ALL.iter().find(|value| format!("{:?}", value) == s).copied()
+                    .ok_or_else(|| format!("unknown {} '{}'", $description, s))
+            }
+        }
+    };
+}
+pub(crate) use from_debug_name;
+
+/// Parses a whole number field of one of the text file formats.
+pub fn parse_number<T: FromStr<Err = ParseIntError>>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number '{}'", value))
 }
@@ -0,0 +1,11 @@
+//! Helpers shared by the integration tests.
+
+use tetrust::util::PieceType;
+use tetrust::{Config, Game};
+
+/// Returns a config whose first piece is of the given type.
+pub fn config_starting_with(kind: PieceType) -> Config {
+    (0..).map(|seed| Config { seed, ..Config::default() })
+        .find(|config| Game::new(*config).piece().kind() == kind)
+        .unwrap()
+}
@@ -0,0 +1,154 @@
+//! Tests of the engine: replays re-simulating a game frame for frame, held inputs and finesse.
+
+mod common;
+
+use common::config_starting_with;
+use std::time::Duration;
+use tetrust::finesse;
+use tetrust::game::FRAME;
+use tetrust::piece::Piece;
+use tetrust::replay::{Playback, Recorder, Replay};
+use tetrust::srs::FlipKicks;
+use tetrust::util::{Direction, PieceType};
+use tetrust::{Config, Game, Input};
+
+/// Steps the game by the given number of frames.
+fn step_frames(game: &mut Game, frames: u32) {
+    for _ in 0..frames {
+        game.step(FRAME);
+    }
+}
+
+#[test]
+fn replay_re_simulates_the_game() {
+    let config = Config { seed: 42, ..Config::default() };
+    let mut recorder = Recorder::new(config);
+    for frame in 0..3000 {
+        // Let gravity and the lock delay place most pieces, with held moves and soft drops in between
+        match frame % 90 {
+            10 => recorder.apply(Input::RotateCw),
+            20 => recorder.press(if frame % 180 == 20 { Input::MoveLeft } else { Input::MoveRight }),
+            30 => recorder.release(Input::MoveLeft),
+            31 => recorder.release(Input::MoveRight),
+            40 => recorder.press(Input::SoftDrop),
+            45 => recorder.release(Input::SoftDrop),
+            50 => recorder.apply(Input::MoveLeft),
+            51 => recorder.repeat(Input::MoveLeft, FRAME * 3 / 2),
+            55 => recorder.release(Input::MoveLeft),
+            60 if frame % 270 == 60 => recorder.apply(Input::Hold),
+            _ => (),
+        }
+        recorder.step();
+    }
+    let game = recorder.game();
+    assert!(game.pieces_placed() >= 10);
+
+    let replay: Replay = recorder.replay().to_string().parse().unwrap();
+    let mut playback = Playback::new(replay);
+    while !playback.is_finished() {
+        playback.step();
+    }
+    let replayed = playback.game();
+    assert_eq!(replayed.board(), game.board());
+    assert_eq!(replayed.score(), game.score());
+    assert_eq!(replayed.pieces_placed(), game.pieces_placed());
+    assert_eq!(replayed.is_game_over(), game.is_game_over());
+}
+
+#[test]
+fn held_move_repeats_after_das() {
+    let config = Config { das: FRAME * 10, arr: FRAME * 2, ..Config::default() };
+    let mut game = Game::new(config);
+    let x = game.piece_position().x;
+
+    game.press(Input::MoveLeft);
+    assert_eq!(game.piece_position().x, x - 1);
+    step_frames(&mut game, 9);
+    assert_eq!(game.piece_position().x, x - 1);
+    step_frames(&mut game, 1);
+    assert_eq!(game.piece_position().x, x - 2);
+    step_frames(&mut game, 2);
+    assert_eq!(game.piece_position().x, x - 3);
+
+    game.release(Input::MoveLeft);
+    step_frames(&mut game, 10);
+    assert_eq!(game.piece_position().x, x - 3);
+}
+
+#[test]
+fn zero_arr_shifts_to_the_wall() {
+    let config = Config { das: FRAME * 10, arr: Duration::ZERO, ..Config::default() };
+    let mut game = Game::new(config);
+    game.press(Input::MoveRight);
+    step_frames(&mut game, 10);
+
+    let x = game.piece_position().x;
+    game.apply(Input::MoveRight);
+    assert_eq!(game.piece_position().x, x, "the piece should already be against the wall");
+}
+
+#[test]
+fn held_soft_drop_speeds_gravity_up() {
+    // Level 1 gravity drops a piece one row per second
+    let config = Config { soft_drop_factor: 20, ..Config::default() };
+    let mut game = Game::new(config);
+    let y = game.piece_position().y;
+
+    game.press(Input::SoftDrop);
+    assert_eq!(game.piece_position().y, y + 1);
+    // A row every 50 ms, and 31 frames are just over 500 ms
+    step_frames(&mut game, 31);
+    assert_eq!(game.piece_position().y, y + 11);
+
+    game.release(Input::SoftDrop);
+    step_frames(&mut game, 31);
+    assert_eq!(game.piece_position().y, y + 11);
+}
+
+#[test]
+fn repeat_holds_a_tap_without_applying_it_again() {
+    let config = Config { das: FRAME * 10, arr: FRAME * 2, ..Config::default() };
+    let mut game = Game::new(config);
+    let x = game.piece_position().x;
+
+    game.apply(Input::MoveRight);
+    game.repeat(Input::MoveRight, Duration::ZERO);
+    assert_eq!(game.piece_position().x, x + 1);
+    step_frames(&mut game, 10);
+    assert_eq!(game.piece_position().x, x + 2);
+}
+
+#[test]
+fn repeat_counts_the_time_since_the_tap_towards_das() {
+    let config = Config { das: FRAME * 10, arr: FRAME * 2, ..Config::default() };
+    let mut game = Game::new(config);
+    let x = game.piece_position().x;
+
+    game.apply(Input::MoveLeft);
+    game.repeat(Input::MoveLeft, FRAME * 8);
+    step_frames(&mut game, 1);
+    assert_eq!(game.piece_position().x, x - 1);
+    step_frames(&mut game, 1);
+    assert_eq!(game.piece_position().x, x - 2);
+}
+
+#[test]
+fn finesse_counts_a_half_turn_as_one_input() {
+    let config = config_starting_with(PieceType::T);
+    let mut piece = Piece::new(PieceType::T);
+    piece.rotate(Direction::Flip);
+    let spawn = Game::new(config).piece_position();
+    assert_eq!(finesse::minimum_inputs(&piece, spawn, FlipKicks::SrsPlus), 1);
+
+    // Two quarter turns where one half turn would do is a fault
+    let mut flipped = Game::new(config);
+    flipped.apply(Input::Rotate180);
+    flipped.apply(Input::HardDrop);
+    assert_eq!(flipped.finesse_faults(), 0);
+
+    let mut turned = Game::new(config);
+    turned.apply(Input::RotateCw);
+    turned.apply(Input::RotateCw);
+    turned.apply(Input::HardDrop);
+    assert_eq!(turned.finesse_faults(), 1);
+}
@@ -0,0 +1,120 @@
+//! Golden snapshot tests of text-rendered game frames. Run with `UPDATE_SNAPSHOTS=1` to write the current
+//! output as the new golden files, then review the diff.
+
+mod common;
+
+use common::config_starting_with;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tetrust::board::{Board, Cell};
+use tetrust::game::FRAME;
+use tetrust::render;
+use tetrust::srs::{FlipKicks, Rotation};
+use tetrust::text::{render_text, TextRenderer};
+use tetrust::util::PieceType;
+use tetrust::{Config, Game, Input};
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(format!("{}.txt", name));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {} (run with UPDATE_SNAPSHOTS=1 to create it)", path.display(), err));
+    assert_eq!(actual, expected, "snapshot '{}' changed", name);
+}
+
+/// A T-spin double slot under an overhang, built from garbage.
+const TSD_SLOT: &str = "
+    .#........
+    #...######
+    ##.#######
+";
+
+#[test]
+fn board_text_round_trips() {
+    let board: Board = TSD_SLOT.parse().unwrap();
+    assert_eq!(board.get(1, 17), Some(Cell::Garbage));
+    assert_eq!(board.get(2, 19), None);
+
+    let mut board = Board::new();
+    board.set(0, 19, Some(Cell::Block(PieceType::T)));
+    board.set(9, 0, Some(Cell::Block(PieceType::I)));
+    board.set(4, 10, Some(Cell::Garbage));
+    assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+}
+
+#[test]
+fn board_parser_rejects_bad_rows() {
+    assert!("..........\n.........".parse::<Board>().is_err());
+    assert!("....X.....".parse::<Board>().is_err());
+    assert!("..........\n".repeat(21).parse::<Board>().is_err());
+    assert!("tT........".parse::<Board>().is_ok());
+}
+
+#[test]
+fn spawn_frame() {
+    assert_snapshot("spawn", &render_text(&Game::new(Config::default())));
+}
+
+#[test]
+fn frame_after_moves_and_hold() {
+    let mut game = Game::new(Config::default());
+    game.apply(Input::Hold);
+    game.apply(Input::MoveLeft);
+    game.apply(Input::MoveLeft);
+    game.apply(Input::RotateCw);
+    for _ in 0..30 {
+        game.step(FRAME);
+    }
+    assert_snapshot("moves_and_hold", &render_text(&game));
+}
+
+#[test]
+fn t_spin_double_scenario() {
+    let mut game = Game::with_board(config_starting_with(PieceType::T), TSD_SLOT.parse().unwrap());
+    assert_snapshot("tsd_before", &render_text(&game));
+
+    game.apply(Input::RotateCw);
+    game.apply(Input::MoveLeft);
+    game.apply(Input::MoveLeft);
+    for _ in 0..20 {
+        game.apply(Input::SoftDrop);
+    }
+    game.apply(Input::RotateCw);
+    game.apply(Input::HardDrop);
+
+    assert_eq!(game.last_clear().unwrap().description(), "T-Spin Double");
+    assert_eq!(game.total_lines(), 2);
+    assert_snapshot("tsd_after", &render_text(&game));
+}
+
+#[test]
+fn flip_kicks_off_the_floor() {
+    let config = config_starting_with(PieceType::T);
+    let mut kicked = Game::new(config);
+    let mut blocked = Game::new(Config { flip_kicks: FlipKicks::None, ..config });
+    for game in [&mut kicked, &mut blocked] {
+        for _ in 0..20 {
+            game.apply(Input::SoftDrop);
+        }
+        game.apply(Input::Rotate180);
+    }
+
+    assert_eq!(kicked.piece().rotation(), Rotation::Flip);
+    assert_eq!(blocked.piece().rotation(), Rotation::Spawn);
+    assert_snapshot("flip_kicked", &render_text(&kicked));
+}
+
+#[test]
+fn paused_frame_hides_the_board() {
+    let game = Game::with_board(Config::default(), TSD_SLOT.parse().unwrap());
+    let options = ["Resume".to_string(), "Restart".to_string(), "Quit".to_string()];
+
+    let mut renderer = TextRenderer::new();
+    render::render_paused(&game, &options, 1, &mut renderer);
+    assert_snapshot("paused", &renderer.to_string());
+}